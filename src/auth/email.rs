@@ -0,0 +1,73 @@
+use rocket::serde::{Deserialize, Serialize};
+
+use super::keys::KeyRing;
+use crate::{AppConfig, entities::users, mailer::Email};
+
+pub const VERIFY_EMAIL: &str = "verify_email";
+pub const RESET_PASSWORD: &str = "reset_password";
+
+/// Claims of a token mailed to a user. A token is only good for its
+/// `purpose`. A reset bumps `token_version`, which is what makes a reset
+/// token single-use.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct EmailClaims {
+    pub sub: String,
+    pub email: String,
+    pub purpose: String,
+    pub token_version: i32,
+}
+
+pub fn sign(keys: &KeyRing, user: &users::Model, purpose: &str, ttl_secs: u64) -> String {
+    keys.sign(
+        EmailClaims {
+            sub: user.user_id.clone(),
+            email: user.email.clone(),
+            purpose: purpose.to_string(),
+            token_version: user.token_version,
+        },
+        ttl_secs,
+    )
+}
+
+/// Claims of a valid, unexpired token for `purpose`.
+pub fn decode_token(keys: &KeyRing, token: &str, purpose: &str) -> Option<EmailClaims> {
+    keys.verify::<EmailClaims>(token)
+        .filter(|claims| claims.purpose == purpose)
+}
+
+pub fn verification_email(config: &AppConfig, keys: &KeyRing, user: &users::Model) -> Email {
+    let token = sign(keys, user, VERIFY_EMAIL, config.email_verification_ttl_secs);
+    Email {
+        from: config.mail_from.clone(),
+        to: user.email.clone(),
+        subject: "Verify your email address".to_string(),
+        body: format!(
+            "Confirm this is your email address by opening the link below.\n\n\
+             {}/verify-email?token={}\n\n\
+             The link expires in {} hours.",
+            config.app_url,
+            token,
+            config.email_verification_ttl_secs / 3600
+        ),
+    }
+}
+
+pub fn reset_email(config: &AppConfig, keys: &KeyRing, user: &users::Model) -> Email {
+    let token = sign(keys, user, RESET_PASSWORD, config.password_reset_ttl_secs);
+    Email {
+        from: config.mail_from.clone(),
+        to: user.email.clone(),
+        subject: "Reset your password".to_string(),
+        body: format!(
+            "Someone asked to reset the password for this account. If it was \
+             you, open the link below to choose a new one.\n\n\
+             {}/reset-password?token={}\n\n\
+             The link expires in {} minutes and works once. If you did not ask \
+             for this, ignore this email.",
+            config.app_url,
+            token,
+            config.password_reset_ttl_secs / 60
+        ),
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::time::SystemTime;
+
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm, OctetKeyPairParameters,
+    OctetKeyPairType, PublicKeyUse,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode};
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use rocket::serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::AppConfig;
+
+/// DER prefix of an Ed25519 `SubjectPublicKeyInfo`; the 32-byte key follows.
+const ED25519_SPKI_PREFIX: [u8; 12] = [0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00];
+
+/// Seconds of clock skew allowed on `exp`, `nbf` and `iat`.
+const LEEWAY_SECS: u64 = 60;
+
+#[derive(Debug)]
+pub struct KeyError(pub String);
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// `claims` wrapped with the registered claims every token carries.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+struct Registered<T> {
+    #[serde(flatten)]
+    claims: T,
+    iss: String,
+    aud: String,
+    iat: u64,
+    nbf: u64,
+    exp: u64,
+}
+
+/// EdDSA keys for signing and verifying tokens, loaded from a directory of
+/// PEM files named `<kid>.pem`. A PKCS#8 private key can sign and verify;
+/// a `<kid>.pub.pem` public key only verifies, which is how a rotated-out
+/// key keeps its tokens valid until they expire. Tokens are signed with
+/// `PAYMENTS_JWT_SIGNING_KID`, or else the private key whose kid sorts
+/// last, so naming keys by date makes the newest one sign.
+pub struct KeyRing {
+    signing_kid: String,
+    signing_key: EncodingKey,
+    verifying: HashMap<String, DecodingKey>,
+    jwks: JwkSet,
+    issuer: String,
+    audience: String,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Label and DER body of a PEM file.
+fn parse_pem(pem: &str) -> Option<(String, Vec<u8>)> {
+    let label = pem
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("-----BEGIN ")?.strip_suffix("-----"))?
+        .to_string();
+    let body: String = pem
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
+    Some((label, STANDARD.decode(body).ok()?))
+}
+
+fn jwk(kid: &str, public_key: &[u8]) -> Jwk {
+    Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(KeyAlgorithm::EdDSA),
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        },
+        algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+            key_type: OctetKeyPairType::OctetKeyPair,
+            curve: EllipticCurve::Ed25519,
+            x: URL_SAFE_NO_PAD.encode(public_key),
+        }),
+    }
+}
+
+impl KeyRing {
+    pub fn load(
+        dir: impl AsRef<Path>,
+        signing_kid: Option<&str>,
+        issuer: &str,
+        audience: &str,
+    ) -> Result<Self, KeyError> {
+        let dir = dir.as_ref();
+        let entries = std::fs::read_dir(dir).map_err(|e| KeyError(format!("{}: {}", dir.display(), e)))?;
+
+        let mut private = HashMap::new();
+        let mut public = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|e| KeyError(e.to_string()))?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let (kid, public_only) = match (name.strip_suffix(".pub.pem"), name.strip_suffix(".pem")) {
+                (Some(kid), _) => (kid.to_string(), true),
+                (None, Some(kid)) => (kid.to_string(), false),
+                _ => continue,
+            };
+
+            let pem = std::fs::read_to_string(&path).map_err(|e| KeyError(format!("{}: {}", name, e)))?;
+            let invalid = || KeyError(format!("{} is not an Ed25519 {} key", name, if public_only { "public" } else { "private" }));
+            let (label, der) = parse_pem(&pem).ok_or_else(invalid)?;
+
+            if public_only {
+                match der.strip_prefix(&ED25519_SPKI_PREFIX[..]) {
+                    Some(key) if label == "PUBLIC KEY" && key.len() == 32 => public.push((kid, key.to_vec())),
+                    _ => return Err(invalid()),
+                }
+            } else {
+                let pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(&der)
+                    .ok()
+                    .filter(|_| label == "PRIVATE KEY")
+                    .ok_or_else(invalid)?;
+                public.push((kid.clone(), pair.public_key().as_ref().to_vec()));
+                private.insert(kid, der);
+            }
+        }
+
+        let signing_kid = match signing_kid {
+            Some(kid) if private.contains_key(kid) => kid.to_string(),
+            Some(kid) => return Err(KeyError(format!("no private key for signing kid '{}'", kid))),
+            None => private
+                .keys()
+                .max()
+                .cloned()
+                .ok_or_else(|| KeyError(format!("no private key in {}", dir.display())))?,
+        };
+
+        public.sort();
+        if let Some(pair) = public.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(KeyError(format!("kid '{}' is used by more than one key", pair[0].0)));
+        }
+
+        let jwks = JwkSet {
+            keys: public.iter().map(|(kid, key)| jwk(kid, key)).collect(),
+        };
+        let verifying = jwks
+            .keys
+            .iter()
+            .map(|jwk| {
+                let kid = jwk.common.key_id.clone().unwrap_or_default();
+                DecodingKey::from_jwk(jwk)
+                    .map(|key| (kid, key))
+                    .map_err(|e| KeyError(e.to_string()))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(KeyRing {
+            signing_key: EncodingKey::from_ed_der(&private[&signing_kid]),
+            signing_kid,
+            verifying,
+            jwks,
+            issuer: issuer.to_string(),
+            audience: audience.to_string(),
+        })
+    }
+
+    /// Public halves of every key, for `/.well-known/jwks.json`.
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
+
+    /// Signs `claims` with the signing key, valid from now for `ttl_secs`.
+    pub fn sign<T: Serialize>(&self, claims: T, ttl_secs: u64) -> String {
+        let now = now();
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some(self.signing_kid.clone());
+
+        encode(
+            &header,
+            &Registered {
+                claims,
+                iss: self.issuer.clone(),
+                aud: self.audience.clone(),
+                iat: now,
+                nbf: now,
+                exp: now + ttl_secs,
+            },
+            &self.signing_key,
+        )
+        .unwrap()
+    }
+
+    /// Claims of `token` if a known key signed it and its issuer, audience
+    /// and validity window all check out.
+    pub fn verify<T: DeserializeOwned>(&self, token: &str) -> Option<T> {
+        let header = decode_header(token).ok()?;
+        if header.alg != Algorithm::EdDSA {
+            return None;
+        }
+        let key = self.verifying.get(header.kid.as_deref()?)?;
+
+        let mut validation = Validation::new(Algorithm::EdDSA);
+        validation.leeway = LEEWAY_SECS;
+        validation.validate_nbf = true;
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        validation.set_required_spec_claims(&["exp", "nbf", "iss", "aud"]);
+
+        let registered = decode::<Registered<T>>(token, key, &validation).ok()?.claims;
+        (registered.iat <= now() + LEEWAY_SECS).then_some(registered.claims)
+    }
+}
+
+/// Loads the key ring from `PAYMENTS_JWT_KEY_DIR`. Startup stops if it has
+/// no usable signing key.
+pub fn build(config: &AppConfig) -> KeyRing {
+    match KeyRing::load(
+        &config.jwt_key_dir,
+        config.jwt_signing_kid.as_deref(),
+        &config.jwt_issuer,
+        &config.jwt_audience,
+    ) {
+        Ok(keys) => keys,
+        Err(e) => panic!("Failed to load JWT keys from {}: {}", config.jwt_key_dir, e),
+    }
+}
@@ -0,0 +1,350 @@
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use chrono::{FixedOffset, Utc};
+use rand::{Rng, rng};
+use rocket::{
+    http::Status,
+    request::{self, FromRequest, Outcome, Request},
+    serde::{Deserialize, Serialize},
+};
+use rust_decimal::Decimal;
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use super::keys::KeyRing;
+use super::throttle::{self, LoginThrottle};
+use crate::{
+    AppConfig,
+    controllers::ErrorResponse,
+    entities::{mfa_recovery_codes, prelude::*, users},
+    fx::{self, FxRateProvider},
+};
+
+const DIGITS: usize = 6;
+const STEP_SECS: u64 = 30;
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const PENDING_PURPOSE: &str = "mfa";
+
+/// Claims of the token a password login returns when MFA is on. Its
+/// `purpose` keeps it from being accepted as an access token.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PendingClaims {
+    pub sub: String,
+    pub purpose: String,
+    pub token_version: i32,
+    /// Device label from the login, kept for the session it turns into.
+    pub device: Option<String>,
+}
+
+/// Step-up check for a request that moves money: the optional `X-MFA-Code`
+/// header, plus the settings, throttle and FX rates it is checked against.
+pub struct StepUp<'r> {
+    code: Option<String>,
+    config: &'r AppConfig,
+    throttle: &'r LoginThrottle,
+    fx_rates: &'r Arc<dyn FxRateProvider>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for StepUp<'r> {
+    type Error = String;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let (Some(config), Some(throttle), Some(fx_rates)) = (
+            req.rocket().state::<AppConfig>(),
+            req.rocket().state::<LoginThrottle>(),
+            req.rocket().state::<Arc<dyn FxRateProvider>>(),
+        ) else {
+            return Outcome::Error((Status::InternalServerError, "Missing step-up state".to_string()));
+        };
+
+        Outcome::Success(StepUp {
+            code: req.headers().get_one("X-MFA-Code").map(str::to_string),
+            config,
+            throttle,
+            fx_rates,
+        })
+    }
+}
+
+/// Signs an "MFA pending" token for a user who has passed the password
+/// check but still owes a code.
+pub fn pending_token(
+    keys: &KeyRing,
+    config: &AppConfig,
+    user: &users::Model,
+    device: Option<String>,
+) -> String {
+    keys.sign(
+        PendingClaims {
+            sub: user.user_id.clone(),
+            purpose: PENDING_PURPOSE.to_string(),
+            token_version: user.token_version,
+            device,
+        },
+        config.mfa_pending_ttl_secs,
+    )
+}
+
+/// Claims of a valid, unexpired pending token.
+pub fn decode_pending_token(keys: &KeyRing, token: &str) -> Option<PendingClaims> {
+    keys.verify::<PendingClaims>(token)
+        .filter(|claims| claims.purpose == PENDING_PURPOSE)
+}
+
+/// A fresh RFC 6238 secret, base32 encoded.
+pub fn new_secret() -> String {
+    Secret::generate_secret().to_encoded().to_string()
+}
+
+fn totp(secret: &str, issuer: &str, email: &str) -> Option<TOTP> {
+    let bytes = Secret::Encoded(secret.to_string()).to_bytes().ok()?;
+    TOTP::new(
+        Algorithm::SHA1,
+        DIGITS,
+        1,
+        STEP_SECS,
+        bytes,
+        Some(issuer.to_string()),
+        email.to_string(),
+    )
+    .ok()
+}
+
+/// `otpauth://` URI for authenticator apps.
+pub fn otpauth_uri(secret: &str, issuer: &str, email: &str) -> Option<String> {
+    totp(secret, issuer, email).map(|totp| totp.get_url())
+}
+
+fn hash(code: &str) -> String {
+    format!("{:x}", Sha256::digest(code.as_bytes()))
+}
+
+/// Recovery codes are compared without dashes, spaces or case.
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Random `XXXX-XXXX-XXXX-XXXX` codes without easily confused characters.
+fn new_recovery_codes() -> Vec<String> {
+    let mut rng = rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let raw: String = (0..16)
+                .map(|_| RECOVERY_CODE_ALPHABET[rng.random_range(0..RECOVERY_CODE_ALPHABET.len())] as char)
+                .collect();
+            format!("{}-{}-{}-{}", &raw[..4], &raw[4..8], &raw[8..12], &raw[12..])
+        })
+        .collect()
+}
+
+/// Replaces `user_id`'s recovery codes with a new set and returns them.
+/// Only their hashes are stored, so this is the one time they are shown.
+pub async fn issue_recovery_codes<C: ConnectionTrait>(db: &C, user_id: &str) -> Result<Vec<String>, DbErr> {
+    let codes = new_recovery_codes();
+
+    MfaRecoveryCodes::delete_many()
+        .filter(mfa_recovery_codes::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+
+    let now = Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap());
+    MfaRecoveryCodes::insert_many(codes.iter().map(|code| mfa_recovery_codes::ActiveModel {
+        user_id: Set(user_id.to_string()),
+        code_hash: Set(hash(&normalize_recovery_code(code))),
+        created_at: Set(now),
+        ..Default::default()
+    }))
+    .exec(db)
+    .await?;
+
+    Ok(codes)
+}
+
+/// Checks `code` against `secret` and, on a match, records its time step
+/// so the same code cannot be used twice. Allows one step of clock skew.
+pub async fn verify_totp<C: ConnectionTrait>(
+    db: &C,
+    user: &users::Model,
+    secret: &str,
+    code: &str,
+) -> Result<bool, DbErr> {
+    let Some(totp) = totp(secret, "", "user") else {
+        return Ok(false);
+    };
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let current = (now / STEP_SECS) as i64;
+
+    for step in [current - 1, current, current + 1] {
+        if user.mfa_last_step.is_some_and(|last| step <= last) {
+            continue;
+        }
+        if totp.generate(step as u64 * STEP_SECS) != code {
+            continue;
+        }
+
+        // Compare-and-set so two requests racing with one code cannot both
+        // succeed.
+        let claimed = Users::update_many()
+            .col_expr(users::Column::MfaLastStep, Expr::value(step))
+            .filter(users::Column::UserId.eq(user.user_id.clone()))
+            .filter(
+                Condition::any()
+                    .add(users::Column::MfaLastStep.is_null())
+                    .add(users::Column::MfaLastStep.lt(step)),
+            )
+            .exec(db)
+            .await?;
+        return Ok(claimed.rows_affected == 1);
+    }
+
+    Ok(false)
+}
+
+/// Accepts either a current TOTP code or an unused recovery code for a user
+/// with MFA enabled. A recovery code is spent by using it.
+pub async fn verify<C: ConnectionTrait>(db: &C, user: &users::Model, code: &str) -> Result<bool, DbErr> {
+    let (true, Some(secret)) = (user.mfa_enabled, user.mfa_secret.as_deref()) else {
+        return Ok(false);
+    };
+
+    let code = code.trim();
+    if code.len() == DIGITS && code.bytes().all(|b| b.is_ascii_digit()) {
+        return verify_totp(db, user, secret, code).await;
+    }
+
+    let spent = MfaRecoveryCodes::update_many()
+        .col_expr(
+            mfa_recovery_codes::Column::UsedAt,
+            Expr::value(Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap())),
+        )
+        .filter(mfa_recovery_codes::Column::UserId.eq(user.user_id.clone()))
+        .filter(mfa_recovery_codes::Column::CodeHash.eq(hash(&normalize_recovery_code(code))))
+        .filter(mfa_recovery_codes::Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+
+    Ok(spent.rows_affected == 1)
+}
+
+/// Recovery codes `user_id` has not used yet.
+pub async fn remaining_recovery_codes<C: ConnectionTrait>(db: &C, user_id: &str) -> Result<u64, DbErr> {
+    MfaRecoveryCodes::find()
+        .filter(mfa_recovery_codes::Column::UserId.eq(user_id))
+        .filter(mfa_recovery_codes::Column::UsedAt.is_null())
+        .count(db)
+        .await
+}
+
+fn step_up_required(message: String) -> ErrorResponse {
+    ErrorResponse((
+        Status::Forbidden,
+        json!({ "status": "mfa_required", "message": message }).to_string(),
+    ))
+}
+
+/// `verify`, counted against the user's throttle subject. Every code check
+/// goes through here, so guesses spread over step-up, `/auth/mfa/verify`
+/// and the MFA settings routes share one limit.
+pub async fn verify_throttled<C: ConnectionTrait>(
+    db: &C,
+    throttle: &LoginThrottle,
+    user: &users::Model,
+    code: &str,
+) -> Result<bool, ErrorResponse> {
+    let subjects = [throttle.mfa_subject(&user.user_id)];
+    if let Some(retry_after) = throttle.retry_after(&subjects).await {
+        return Err(ErrorResponse((
+            Status::TooManyRequests,
+            json!({
+                "status": "mfa_throttled",
+                "message": "Too many wrong two-factor codes. Try again later.",
+                "retry_after": retry_after,
+            })
+            .to_string(),
+        )));
+    }
+
+    if !verify(db, user, code).await? {
+        let locked = throttle.fail(&subjects).await;
+        if !locked.is_empty() {
+            throttle::record_lockouts(db, &user.email, None, locked).await?;
+        }
+        return Ok(false);
+    }
+
+    throttle.succeed(&subjects).await;
+    Ok(true)
+}
+
+impl StepUp<'_> {
+    /// Money leaving an account above `config.mfa_step_up_amount` needs a
+    /// current code in `X-MFA-Code`, so a stolen access token alone cannot
+    /// move large sums. The threshold is in `config.mfa_step_up_currency`;
+    /// amounts in other currencies are converted before comparing, and an
+    /// amount that cannot be priced always needs the code.
+    pub async fn require<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        user_id: &str,
+        amount: Decimal,
+        currency: &str,
+    ) -> Result<(), ErrorResponse> {
+        let threshold = self.config.mfa_step_up_amount;
+        let threshold_currency = &self.config.mfa_step_up_currency;
+        let converted = self.in_threshold_currency(amount, currency).await;
+        if converted.is_some_and(|converted| converted <= threshold) {
+            return Ok(());
+        }
+
+        let user = Users::find_by_id(user_id.to_string())
+            .one(db)
+            .await?
+            .ok_or_else(|| ErrorResponse((Status::Unauthorized, "User not found".to_string())))?;
+
+        if !user.mfa_enabled {
+            return Err(step_up_required(format!(
+                "Enable two-factor authentication to move more than {} {}.",
+                threshold, threshold_currency
+            )));
+        }
+        let Some(code) = self.code.as_deref() else {
+            return Err(step_up_required(format!(
+                "Amounts above {} {} need a code in the X-MFA-Code header.",
+                threshold, threshold_currency
+            )));
+        };
+        if !verify_throttled(db, self.throttle, &user, code).await? {
+            return Err(step_up_required("Invalid two-factor code.".to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn in_threshold_currency(&self, amount: Decimal, currency: &str) -> Option<Decimal> {
+        let threshold_currency = &self.config.mfa_step_up_currency;
+        if currency == threshold_currency {
+            return Some(amount);
+        }
+        match self.fx_rates.rate(currency, threshold_currency).await {
+            Ok(Some(rate)) => Some(fx::convert(amount, rate, threshold_currency)),
+            Ok(None) => None,
+            Err(e) => {
+                eprintln!("FX rate lookup for step-up failed: {}", e);
+                None
+            }
+        }
+    }
+}
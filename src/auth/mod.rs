@@ -1,4 +1,3 @@
-use jsonwebtoken::{DecodingKey, Validation, decode};
 use rocket::{
     http::Status,
     request::{self, FromRequest, Outcome, Request},
@@ -6,21 +5,44 @@ use rocket::{
 };
 use sea_orm::{DatabaseConnection, EntityTrait, QueryFilter, ColumnTrait};
 
-use crate::{
-    AppConfig,
-    entities::{prelude::Users, users},
-};
+use crate::entities::{prelude::Users, users};
+use keys::KeyRing;
+
+pub mod email;
+pub mod keys;
+pub mod mfa;
+pub mod password;
+pub mod sessions;
+pub mod throttle;
+
+/// `purpose` claim of an access token. Every kind of token carries a
+/// distinct `purpose` and is only accepted where that purpose is checked.
+pub const ACCESS_PURPOSE: &str = "access";
+
+pub mod role {
+    pub const USER: &str = "user";
+    pub const SUPPORT: &str = "support";
+    pub const ADMIN: &str = "admin";
+}
 
 pub struct AuthenticatedUser {
     pub id: String,
+    pub role: String,
 }
 
+/// Back-office staff: support agents and admins. Read-only admin routes
+/// take this guard.
+pub struct SupportUser(pub AuthenticatedUser);
+
+/// Admins only. Admin routes that change data take this guard.
+pub struct AdminUser(pub AuthenticatedUser);
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct Claims {
     pub sub: String,
+    pub purpose: String,
     pub role: String,
-    pub exp: u64,
     pub token_version: i32,
 }
 
@@ -34,27 +56,23 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
             None => return Outcome::Error((Status::Unauthorized, "Token absent".to_string())),
         };
 
-        let config = match req.rocket().state::<AppConfig>() {
-            Some(cfg) => cfg,
-            None => return Outcome::Error((Status::InternalServerError, "Missing config".to_string())),
+        let keys = match req.rocket().state::<KeyRing>() {
+            Some(keys) => keys,
+            None => return Outcome::Error((Status::InternalServerError, "Missing signing keys".to_string())),
         };
 
         let db = match req.rocket().state::<DatabaseConnection>() {
             Some(d) => d,
             None => return Outcome::Error((Status::InternalServerError, "Missing DB connection".to_string())),
         };
-
-        let token_data = match decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(config.jwt_secret.as_bytes()),
-            &Validation::new(jsonwebtoken::Algorithm::HS256),
-        ) {
-            Ok(data) => data,
-            Err(_) => return Outcome::Error((Status::Unauthorized, "Invalid token".to_string())),
+        // Checks the signature, kid, issuer, audience and validity window,
+        // then that this is an access token and not another kind.
+        // Checks the signature, kid, issuer, audience and validity window.
+        let claims = match keys.verify::<Claims>(token) {
+            Some(claims) if claims.purpose == ACCESS_PURPOSE => claims,
+            _ => return Outcome::Error((Status::Unauthorized, "Invalid token".to_string())),
         };
 
-        let claims = token_data.claims;
-
         let user = match Users::find()
             .filter(users::Column::UserId.eq(&claims.sub))
             .one(db)
@@ -68,6 +86,43 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
             return Outcome::Error((Status::Unauthorized, "Token expired or invalidated".to_string()));
         }
 
-        Outcome::Success(AuthenticatedUser { id: claims.sub })
+        // The role is read from the user row rather than the token so that
+        // a demotion takes effect immediately.
+        Outcome::Success(AuthenticatedUser {
+            id: claims.sub,
+            role: user.role,
+        })
+    }
+}
+
+/// Authenticates the caller and then requires one of `roles`; anyone else
+/// gets 403.
+async fn require_role(
+    req: &Request<'_>,
+    roles: &[&str],
+) -> request::Outcome<AuthenticatedUser, String> {
+    match req.guard::<AuthenticatedUser>().await {
+        Outcome::Success(user) if roles.contains(&user.role.as_str()) => Outcome::Success(user),
+        Outcome::Success(_) => Outcome::Error((Status::Forbidden, "Insufficient role".to_string())),
+        Outcome::Error(e) => Outcome::Error(e),
+        Outcome::Forward(status) => Outcome::Forward(status),
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for SupportUser {
+    type Error = String;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        require_role(req, &[role::SUPPORT, role::ADMIN]).await.map(SupportUser)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminUser {
+    type Error = String;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        require_role(req, &[role::ADMIN]).await.map(AdminUser)
     }
 }
@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+
+use serde_json::{Value, json};
+
+use crate::AppConfig;
+
+/// bcrypt ignores everything past its first 72 bytes.
+pub const MAX_BYTES: usize = 72;
+
+/// Rules a new password must meet. Built once at startup; the blocklist
+/// file is read then.
+pub struct PasswordPolicy {
+    min_length: usize,
+    min_classes: usize,
+    blocklist: HashSet<String>,
+}
+
+impl PasswordPolicy {
+    pub fn new(min_length: usize, min_classes: usize, blocklist: HashSet<String>) -> Self {
+        Self {
+            min_length,
+            min_classes,
+            blocklist,
+        }
+    }
+
+    /// Reads the blocklist from `path`: one password per line, compared
+    /// case-insensitively. Blank lines and lines starting with `#` are
+    /// skipped.
+    pub fn load_blocklist(path: &str) -> Result<HashSet<String>, std::io::Error> {
+        Ok(std::fs::read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_lowercase)
+            .collect())
+    }
+
+    /// Every rule `password` breaks for the user with `email`; empty when
+    /// it is acceptable.
+    pub fn violations(&self, password: &str, email: &str) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        if password.chars().count() < self.min_length {
+            violations.push(format!("must be at least {} characters long", self.min_length));
+        }
+        if password.len() > MAX_BYTES {
+            violations.push(format!("must be at most {} bytes long", MAX_BYTES));
+        }
+
+        let classes = [
+            password.chars().any(|c| c.is_lowercase()),
+            password.chars().any(|c| c.is_uppercase()),
+            password.chars().any(|c| c.is_numeric()),
+            password.chars().any(|c| !c.is_alphanumeric()),
+        ]
+        .into_iter()
+        .filter(|&present| present)
+        .count();
+        if classes < self.min_classes {
+            violations.push(format!(
+                "must mix at least {} of lowercase letters, uppercase letters, digits and symbols",
+                self.min_classes
+            ));
+        }
+
+        let lowered = password.to_lowercase();
+        if self.blocklist.contains(&lowered) {
+            violations.push("is too common; choose a less guessable password".to_string());
+        }
+
+        // Short local parts like "jo" would reject too many passwords.
+        let email = email.to_lowercase();
+        let local_part = email.split('@').next().unwrap_or_default();
+        if local_part.chars().count() >= 3 && lowered.contains(local_part) {
+            violations.push("must not contain your email address".to_string());
+        }
+
+        violations
+    }
+
+    /// `violations` as a validation error body for `field`, in the shape
+    /// `format_validation_errors_json` produces.
+    pub fn check(&self, field: &str, password: &str, email: &str) -> Result<(), Value> {
+        let violations = self.violations(password, email);
+        if violations.is_empty() {
+            return Ok(());
+        }
+        Err(json!({
+            "errors": violations
+                .into_iter()
+                .map(|message| json!({ "field": field, "message": message }))
+                .collect::<Vec<Value>>()
+        }))
+    }
+}
+
+/// Builds the policy from `PAYMENTS_PASSWORD_*` settings. A configured
+/// blocklist file that cannot be read stops startup.
+pub fn build(config: &AppConfig) -> PasswordPolicy {
+    let blocklist = match &config.password_blocklist_file {
+        Some(path) => match PasswordPolicy::load_blocklist(path) {
+            Ok(blocklist) => blocklist,
+            Err(e) => panic!("Failed to load the password blocklist from {}: {}", path, e),
+        },
+        None => HashSet::new(),
+    };
+    PasswordPolicy::new(config.password_min_length, config.password_min_classes, blocklist)
+}
@@ -0,0 +1,141 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{FixedOffset, Utc};
+use rand::{RngCore, rng};
+use sea_orm::sea_query::Expr;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::*;
+use sha2::{Digest, Sha256};
+
+use crate::entities::{prelude::RefreshTokens, refresh_tokens};
+
+/// Outcome of presenting a refresh token.
+pub enum Rotation {
+    /// The token was valid; it is now spent and `token` replaces it.
+    Rotated { user_id: String, token: String },
+    /// Unknown, expired or revoked.
+    Invalid,
+    /// The token had already been rotated, so someone is replaying it. Its
+    /// whole family has been revoked.
+    Reused,
+}
+
+/// Only the hash is stored, so a leaked table cannot be replayed.
+fn hash(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn random_string(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn now() -> DateTimeWithTimeZone {
+    Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap())
+}
+
+/// Stores a new refresh token for `user_id` and returns it with its row.
+/// Each login starts a new family (one per device session); rotations stay
+/// in the family they came from.
+pub async fn issue<C: ConnectionTrait>(
+    db: &C,
+    user_id: &str,
+    family_id: Option<String>,
+    device: Option<String>,
+    ttl_secs: i64,
+) -> Result<(String, refresh_tokens::Model), DbErr> {
+    let token = random_string(32);
+    let now = now();
+
+    let row = refresh_tokens::ActiveModel {
+        user_id: Set(user_id.to_string()),
+        family_id: Set(family_id.unwrap_or_else(|| format!("fam-{}", random_string(16)))),
+        token_hash: Set(hash(&token)),
+        device: Set(device),
+        expires_at: Set(now + chrono::Duration::seconds(ttl_secs)),
+        created_at: Set(now),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok((token, row))
+}
+
+/// Spends `presented` and issues its successor in the same family. The row
+/// is locked so two requests racing with one token cannot both rotate it;
+/// the loser is treated as a replay.
+pub async fn rotate(db: &DatabaseConnection, presented: &str, ttl_secs: i64) -> Result<Rotation, DbErr> {
+    let db_txn = db.begin().await?;
+
+    let Some(current) = RefreshTokens::find()
+        .filter(refresh_tokens::Column::TokenHash.eq(hash(presented)))
+        .lock_exclusive()
+        .one(&db_txn)
+        .await?
+    else {
+        return Ok(Rotation::Invalid);
+    };
+
+    if current.replaced_by.is_some() {
+        revoke_family(&db_txn, &current.family_id).await?;
+        db_txn.commit().await?;
+        return Ok(Rotation::Reused);
+    }
+    if current.revoked_at.is_some() || current.expires_at <= Utc::now() {
+        return Ok(Rotation::Invalid);
+    }
+
+    let (token, next) = issue(
+        &db_txn,
+        &current.user_id,
+        Some(current.family_id.clone()),
+        current.device.clone(),
+        ttl_secs,
+    )
+    .await?;
+
+    let user_id = current.user_id.clone();
+    let mut spent = current.into_active_model();
+    spent.revoked_at = Set(Some(now()));
+    spent.replaced_by = Set(Some(next.token_id));
+    spent.update(&db_txn).await?;
+
+    db_txn.commit().await?;
+
+    Ok(Rotation::Rotated { user_id, token })
+}
+
+/// Looks up the unrevoked token `presented`, if any.
+pub async fn find_active<C: ConnectionTrait>(
+    db: &C,
+    presented: &str,
+) -> Result<Option<refresh_tokens::Model>, DbErr> {
+    RefreshTokens::find()
+        .filter(refresh_tokens::Column::TokenHash.eq(hash(presented)))
+        .filter(refresh_tokens::Column::RevokedAt.is_null())
+        .one(db)
+        .await
+}
+
+/// Ends one device session.
+pub async fn revoke_family<C: ConnectionTrait>(db: &C, family_id: &str) -> Result<(), DbErr> {
+    RefreshTokens::update_many()
+        .col_expr(refresh_tokens::Column::RevokedAt, Expr::value(now()))
+        .filter(refresh_tokens::Column::FamilyId.eq(family_id))
+        .filter(refresh_tokens::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Ends every session `user_id` has.
+pub async fn revoke_all<C: ConnectionTrait>(db: &C, user_id: &str) -> Result<(), DbErr> {
+    RefreshTokens::update_many()
+        .col_expr(refresh_tokens::Column::RevokedAt, Expr::value(now()))
+        .filter(refresh_tokens::Column::UserId.eq(user_id))
+        .filter(refresh_tokens::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(())
+}
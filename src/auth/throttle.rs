@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use sea_orm::*;
+
+use crate::AppConfig;
+use crate::entities::login_lockouts;
+
+/// Failed logins counted against one subject (an email or a client IP).
+#[derive(Debug, Clone, Default)]
+pub struct Attempts {
+    pub failures: u32,
+    pub last_failure_at: Option<DateTime<Utc>>,
+    pub blocked_until: Option<DateTime<Utc>>,
+}
+
+/// Where failed-login counters live. Each method must be atomic per key so
+/// concurrent attempts cannot lose a failure.
+#[rocket::async_trait]
+pub trait AttemptStore: Send + Sync {
+    async fn get(&self, key: &str) -> Option<Attempts>;
+    /// Counts one more failure for `key`, restarting from zero when the last
+    /// one is older than `forget_after`, and returns the new count.
+    async fn record_failure(&self, key: &str, now: DateTime<Utc>, forget_after: Duration) -> u32;
+    async fn block(&self, key: &str, until: DateTime<Utc>);
+    async fn clear(&self, key: &str);
+}
+
+/// Process-local store. Counters reset on restart and are not shared
+/// between instances.
+#[derive(Default)]
+pub struct MemoryAttemptStore(Mutex<HashMap<String, Attempts>>);
+
+const MEMORY_STORE_CAPACITY: usize = 100_000;
+
+#[rocket::async_trait]
+impl AttemptStore for MemoryAttemptStore {
+    async fn get(&self, key: &str) -> Option<Attempts> {
+        self.0.lock().unwrap().get(key).cloned()
+    }
+
+    async fn record_failure(&self, key: &str, now: DateTime<Utc>, forget_after: Duration) -> u32 {
+        let mut attempts = self.0.lock().unwrap();
+        let stale = |a: &Attempts| a.last_failure_at.is_none_or(|at| now - at > forget_after);
+
+        if attempts.len() >= MEMORY_STORE_CAPACITY {
+            attempts.retain(|_, a| !stale(a) || a.blocked_until.is_some_and(|until| until > now));
+        }
+
+        let entry = attempts.entry(key.to_string()).or_default();
+        if stale(entry) {
+            entry.failures = 0;
+        }
+        entry.failures += 1;
+        entry.last_failure_at = Some(now);
+        entry.failures
+    }
+
+    async fn block(&self, key: &str, until: DateTime<Utc>) {
+        self.0.lock().unwrap().entry(key.to_string()).or_default().blocked_until = Some(until);
+    }
+
+    async fn clear(&self, key: &str) {
+        self.0.lock().unwrap().remove(key);
+    }
+}
+
+/// A login subject and how many failures it may have before it is locked.
+pub struct Subject {
+    pub key: String,
+    pub max_failures: u32,
+}
+
+/// Brute-force protection for `POST /auth/login`. Every failure blocks the
+/// subject for an exponentially growing delay; reaching `max_failures`
+/// locks it out for `lockout_secs` and records the lockout.
+pub struct LoginThrottle {
+    store: Arc<dyn AttemptStore>,
+    max_failures: u32,
+    ip_max_failures: u32,
+    backoff_base_secs: u64,
+    lockout_secs: u64,
+}
+
+impl LoginThrottle {
+    pub fn new(store: Arc<dyn AttemptStore>, config: &AppConfig) -> Self {
+        Self {
+            store,
+            max_failures: config.login_max_failures,
+            ip_max_failures: config.login_ip_max_failures,
+            backoff_base_secs: config.login_backoff_base_secs,
+            lockout_secs: config.login_lockout_secs,
+        }
+    }
+
+    /// The subjects a login for `email` from `ip` counts against.
+    pub fn subjects(&self, email: &str, ip: Option<String>) -> Vec<Subject> {
+        let mut subjects = vec![Subject {
+            key: format!("email:{}", email.to_lowercase()),
+            max_failures: self.max_failures,
+        }];
+        if let Some(ip) = ip {
+            subjects.push(Subject {
+                key: format!("ip:{}", ip),
+                max_failures: self.ip_max_failures,
+            });
+        }
+        subjects
+    }
+
+    /// The subject every two-factor code check for `user_id` counts
+    /// against, wherever the code was entered.
+    pub fn mfa_subject(&self, user_id: &str) -> Subject {
+        Subject {
+            key: format!("mfa:{}", user_id),
+            max_failures: self.max_failures,
+        }
+    }
+
+    /// Seconds until the caller may try again, if any subject is blocked.
+    pub async fn retry_after(&self, subjects: &[Subject]) -> Option<u64> {
+        let now = Utc::now();
+        let mut wait = None;
+        for subject in subjects {
+            if let Some(until) = self.store.get(&subject.key).await.and_then(|a| a.blocked_until)
+                && until > now
+            {
+                // Round up so a client that waits exactly this long gets in.
+                let secs = ((until - now).num_milliseconds() as u64).div_ceil(1000);
+                wait = wait.max(Some(secs));
+            }
+        }
+        wait
+    }
+
+    /// Counts a failed login against every subject and blocks each one for
+    /// its backoff delay. Returns the subjects that are now locked out, with
+    /// their failure count and lock end.
+    pub async fn fail(&self, subjects: &[Subject]) -> Vec<(String, u32, DateTime<Utc>)> {
+        let now = Utc::now();
+        let forget_after = Duration::seconds(self.lockout_secs as i64);
+        let mut locked = Vec::new();
+
+        for subject in subjects {
+            let failures = self.store.record_failure(&subject.key, now, forget_after).await;
+            let delay = if failures >= subject.max_failures {
+                self.lockout_secs
+            } else {
+                self.backoff_base_secs
+                    .saturating_mul(1u64 << (failures - 1).min(32))
+                    .min(self.lockout_secs)
+            };
+            if delay == 0 {
+                continue;
+            }
+
+            let until = now + Duration::seconds(delay as i64);
+            self.store.block(&subject.key, until).await;
+            if failures >= subject.max_failures {
+                locked.push((subject.key.clone(), failures, until));
+            }
+        }
+
+        locked
+    }
+
+    /// A correct password resets the email's counter. The IP counter is left
+    /// alone so one valid account cannot be used to keep guessing others.
+    pub async fn succeed(&self, subjects: &[Subject]) {
+        if let Some(subject) = subjects.first() {
+            self.store.clear(&subject.key).await;
+        }
+    }
+}
+
+/// Writes one audit row per lockout.
+pub async fn record_lockouts<C: ConnectionTrait>(
+    db: &C,
+    email: &str,
+    ip: Option<&str>,
+    locked: Vec<(String, u32, DateTime<Utc>)>,
+) -> Result<(), DbErr> {
+    let offset = FixedOffset::east_opt(0).unwrap();
+
+    for (subject, failures, until) in locked {
+        println!("Login locked out for {} until {}", subject, until);
+        login_lockouts::ActiveModel {
+            subject: Set(subject),
+            email: Set(Some(email.to_string())),
+            ip_address: Set(ip.map(str::to_string)),
+            failures: Set(failures as i32),
+            locked_until: Set(until.with_timezone(&offset)),
+            created_at: Set(Utc::now().with_timezone(&offset)),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+    }
+
+    Ok(())
+}
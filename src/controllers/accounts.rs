@@ -1,33 +1,74 @@
 use super::Response;
+use super::transactions::parse_timestamp;
 use super::{ErrorResponse, SuccessResponse};
+use crate::statements;
+use crate::utils::validations::{format_validation_errors_json, is_valid_currency_code};
 use crate::{
+    AppConfig,
     auth::AuthenticatedUser,
     entities::{account, prelude::*},
 };
+use chrono::{FixedOffset, Utc};
+use garde::Validate;
 use rocket::{
     State,
-    http::Status,
-    serde::{Serialize, json::Json},
+    http::{ContentType, Header, Status},
+    response::stream::TextStream,
+    serde::{Deserialize, Serialize, json::Json},
 };
+use rust_decimal::Decimal;
 use sea_orm::*;
+use serde_json::{Value, json};
+
+/// Accounts owned by `user_id`, narrowed to `account_id` when one is given.
+pub fn user_accounts(user_id: &str, account_id: Option<&str>) -> Select<Account> {
+    let query = Account::find().filter(account::Column::UserId.eq(user_id));
+    match account_id {
+        Some(account_id) => query.filter(account::Column::AccountId.eq(account_id)),
+        None => query,
+    }
+}
+
+/// Picks the account a request acts on from the rows matched by
+/// [`user_accounts`] (queried with `.limit(2)`). An explicit `account_id`
+/// is only required once the user has more than one account.
+pub fn single_account(mut found: Vec<account::Model>) -> Result<account::Model, ErrorResponse> {
+    match found.len() {
+        0 => Err(ErrorResponse((
+            Status::NotFound,
+            "Account not found for the user.".to_string(),
+        ))),
+        1 => Ok(found.remove(0)),
+        _ => Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            "account_id is required when you have more than one account.".to_string(),
+        ))),
+    }
+}
 
 #[derive(Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct BalanceResponse {
     status: String,
-    balance: f32,
+    #[serde(with = "rust_decimal::serde::str")]
+    balance: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    locked_balance: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    available_balance: Decimal,
     currency_code: String,
 }
 
-#[get("/balance")]
+#[get("/balance?<account_id>")]
 pub async fn balance(
     db: &State<DatabaseConnection>,
     user: AuthenticatedUser,
+    account_id: Option<&str>,
 ) -> Response<Json<BalanceResponse>> {
     let db = db as &DatabaseConnection;
-    let account = Account::find()
-        .filter(account::Column::UserId.eq(user.id))
-        .one(db)
+    let found = user_accounts(&user.id, account_id)
+        .limit(2)
+        .all(db)
         .await
         .map_err(|_| {
             ErrorResponse((
@@ -35,19 +76,281 @@ pub async fn balance(
                 "Failed to retrieve account information.".to_string(),
             ))
         })?;
+    let acc = single_account(found)?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(BalanceResponse {
+            status: "success".to_string(),
+            balance: acc.balance,
+            locked_balance: acc.locked_balance,
+            available_balance: acc.balance - acc.locked_balance,
+            currency_code: acc.currency_code,
+        }),
+    )))
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AccountData {
+    account_id: String,
+    currency_code: String,
+    #[serde(with = "rust_decimal::serde::str")]
+    balance: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    locked_balance: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    available_balance: Decimal,
+    status: String,
+    updated_at: String,
+}
+
+impl From<account::Model> for AccountData {
+    fn from(acc: account::Model) -> Self {
+        AccountData {
+            available_balance: acc.balance - acc.locked_balance,
+            account_id: acc.account_id,
+            currency_code: acc.currency_code,
+            balance: acc.balance,
+            locked_balance: acc.locked_balance,
+            status: acc.status,
+            updated_at: acc.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AccountResponse {
+    status: String,
+    message: String,
+    account: AccountData,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AccountListResponse {
+    status: String,
+    accounts: Vec<AccountData>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(crate = "rocket::serde")]
+pub struct OpenAccountRequest {
+    #[garde(custom(is_valid_currency_code))]
+    currency_code: String,
+}
+
+#[post("/", data = "<open_req>")]
+pub async fn open_account(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    open_req: Json<OpenAccountRequest>,
+) -> Response<Json<AccountResponse>> {
+    let db = db as &DatabaseConnection;
+
+    if let Err(report) = open_req.validate() {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            format_validation_errors_json(report).to_string(),
+        )));
+    }
 
-    match account {
-        Some(acc) => Ok(SuccessResponse((
-            Status::Ok,
-            Json(BalanceResponse {
+    let opened = account::ActiveModel {
+        user_id: Set(user.id.clone()),
+        currency_code: Set(open_req.currency_code.clone()),
+        balance: Set(Decimal::ZERO),
+        locked_balance: Set(Decimal::ZERO),
+        updated_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap())),
+        ..Default::default()
+    }
+    .insert(db)
+    .await;
+
+    match opened {
+        Ok(acc) => Ok(SuccessResponse((
+            Status::Created,
+            Json(AccountResponse {
                 status: "success".to_string(),
-                balance: acc.balance,
-                currency_code: acc.currency_code,
+                message: "Account opened.".to_string(),
+                account: acc.into(),
             }),
         ))),
-        None => Err(ErrorResponse((
-            Status::NotFound,
-            "Account not found for the given user ID.".to_string(),
-        ))),
+        Err(err) if matches!(err.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+            Err(ErrorResponse((
+                Status::Conflict,
+                format!("You already have a {} account.", open_req.currency_code),
+            )))
+        }
+        Err(err) => Err(err.into()),
     }
 }
+
+#[get("/")]
+pub async fn list_accounts(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+) -> Response<Json<AccountListResponse>> {
+    let accounts = user_accounts(&user.id, None)
+        .order_by_asc(account::Column::CurrencyCode)
+        .all(db as &DatabaseConnection)
+        .await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(AccountListResponse {
+            status: "success".to_string(),
+            accounts: accounts.into_iter().map(AccountData::from).collect(),
+        }),
+    )))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum StatementFormat {
+    Csv,
+    Jsonl,
+}
+
+#[derive(Responder)]
+pub struct StatementFile<T> {
+    body: T,
+    content_type: ContentType,
+    disposition: Header<'static>,
+}
+
+fn statement_row(format: StatementFormat, row: Value) -> String {
+    match format {
+        StatementFormat::Jsonl => format!("{}\n", row),
+        StatementFormat::Csv => {
+            let cells: Vec<String> = STATEMENT_COLUMNS
+                .iter()
+                .map(|column| match &row[*column] {
+                    Value::Null => String::new(),
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .collect();
+            format!("{}\n", cells.join(","))
+        }
+    }
+}
+
+const STATEMENT_COLUMNS: [&str; 9] = [
+    "record",
+    "as_of",
+    "txn_id",
+    "txn_type",
+    "status",
+    "counterparty_account_id",
+    "amount",
+    "balance",
+    "currency_code",
+];
+
+/// Streams a statement for one of the caller's accounts: an opening
+/// balance, every settled txn in `[from, to)` with the running balance after
+/// it, and a closing balance. Rows are read in pages of
+/// `config.statement_page_size` rather than from one long-lived snapshot, so a
+/// slow client never pins a pooled connection; the closing balance is the
+/// opening plus the rows listed, so the file always adds up.
+#[get("/<account_id>/statement?<from>&<to>&<format>")]
+pub async fn statement(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    account_id: &str,
+    from: Option<&str>,
+    to: Option<&str>,
+    format: Option<&str>,
+) -> Response<StatementFile<TextStream![String]>> {
+    let format = match format.unwrap_or("csv") {
+        "csv" => StatementFormat::Csv,
+        "jsonl" => StatementFormat::Jsonl,
+        other => {
+            return Err(ErrorResponse((
+                Status::UnprocessableEntity,
+                format!("Unknown statement format '{}'. Use csv or jsonl.", other),
+            )));
+        }
+    };
+    let from = parse_timestamp("from", from)?;
+    let to = parse_timestamp("to", to)?;
+
+    // Fixed up front so txns created while the statement streams are left out.
+    let to = to.unwrap_or_else(|| Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap()));
+    let db = db.inner().clone();
+    let page_size = config.statement_page_size;
+
+    let account = user_accounts(&user.id, Some(account_id))
+        .one(&db)
+        .await?
+        .ok_or_else(|| ErrorResponse((Status::NotFound, "Account not found for the user.".to_string())))?;
+    let opening = statements::opening_balance(&db, &account.account_id, from).await?;
+
+    let (content_type, extension) = match format {
+        StatementFormat::Csv => (ContentType::CSV, "csv"),
+        StatementFormat::Jsonl => (ContentType::new("application", "x-ndjson"), "jsonl"),
+    };
+    let disposition = Header::new(
+        "Content-Disposition",
+        format!("attachment; filename=\"statement-{}.{}\"", account.account_id, extension),
+    );
+
+    let body = TextStream! {
+        let currency_code = account.currency_code;
+        let row = |record: &str, as_of: Option<String>, balance: Decimal| json!({
+            "record": record,
+            "as_of": as_of,
+            "balance": balance.to_string(),
+            "currency_code": currency_code,
+        });
+
+        if format == StatementFormat::Csv {
+            yield format!("{}\n", STATEMENT_COLUMNS.join(","));
+        }
+        yield statement_row(format, row("opening", from.map(|f| f.to_rfc3339()), opening));
+
+        let mut balance = opening;
+        let mut after = None;
+        loop {
+            let page = match statements::lines(&db, &account.account_id, from, to, after.as_ref(), page_size).await {
+                Ok(page) => page,
+                Err(e) => {
+                    eprintln!("Failed to stream statement for {}: {}", account.account_id, e);
+                    return;
+                }
+            };
+            let full = page.len() as u64 == page_size;
+
+            for line in page {
+                balance += line.amount;
+                yield statement_row(format, json!({
+                    "record": "txn",
+                    "as_of": line.created_at.to_rfc3339(),
+                    "txn_id": line.txn_id,
+                    "txn_type": line.txn_type,
+                    "status": line.status,
+                    "counterparty_account_id": line.counterparty_account_id,
+                    "amount": line.amount.to_string(),
+                    "balance": balance.to_string(),
+                    "currency_code": currency_code,
+                }));
+                after = Some(line);
+            }
+            if !full {
+                break;
+            }
+        }
+
+        yield statement_row(format, row("closing", Some(to.to_rfc3339()), balance));
+    };
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        StatementFile {
+            body,
+            content_type,
+            disposition,
+        },
+    )))
+}
@@ -0,0 +1,263 @@
+use super::Response;
+use super::accounts::AccountData;
+use super::transactions::TransactionData;
+use super::{ErrorResponse, SuccessResponse};
+use crate::{
+    auth::{AdminUser, SupportUser},
+    entities::{account, admin_audit_log, prelude::*, users},
+};
+use chrono::{FixedOffset, Utc};
+use rocket::{
+    State,
+    http::Status,
+    serde::{Serialize, json::Json},
+};
+use sea_orm::sea_query::{Expr, LikeExpr};
+use sea_orm::*;
+
+const MAX_SEARCH_RESULTS: u64 = 50;
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AdminUserData {
+    user_id: String,
+    email: String,
+    role: String,
+    kyc_status: String,
+    created_at: String,
+    accounts: Vec<AccountData>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UserSearchResponse {
+    status: String,
+    users: Vec<AdminUserData>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AdminAccountResponse {
+    status: String,
+    user_id: String,
+    account: AccountData,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AdminTransactionResponse {
+    status: String,
+    user_id: String,
+    transaction: TransactionData,
+}
+
+fn account_not_found(account_id: &str) -> ErrorResponse {
+    ErrorResponse((Status::NotFound, format!("Account with ID {} not found.", account_id)))
+}
+
+/// Writes the audit row for a staff read or change. Changes pass their
+/// transaction so the row commits or rolls back with them.
+async fn audit<C: ConnectionTrait>(
+    db: &C,
+    staff_user_id: &str,
+    action: &str,
+    target: &str,
+) -> Result<(), DbErr> {
+    admin_audit_log::ActiveModel {
+        staff_user_id: Set(staff_user_id.to_string()),
+        action: Set(action.to_string()),
+        target: Set(target.to_string()),
+        created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap())),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    Ok(())
+}
+
+/// `%`, `_` and the escape character itself match literally in `pattern`.
+fn escape_like(pattern: &str) -> String {
+    let mut escaped = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Users whose email contains `email`, with their accounts. `email` is
+/// matched literally, so wildcards cannot list every user.
+#[get("/users?<email>")]
+pub async fn search_users(
+    db: &State<DatabaseConnection>,
+    staff: SupportUser,
+    email: &str,
+) -> Response<Json<UserSearchResponse>> {
+    let db = db as &DatabaseConnection;
+    println!("User search for '{}' by {}", email, staff.0.id);
+    audit(db, &staff.0.id, "search_users", email).await?;
+
+    let found = Users::find()
+        .filter(
+            Expr::col((users::Entity, users::Column::Email))
+                .like(LikeExpr::new(format!("%{}%", escape_like(email))).escape('\\')),
+        )
+        .order_by_asc(users::Column::Email)
+        .limit(MAX_SEARCH_RESULTS)
+        .all(db)
+        .await?;
+    let accounts = found.load_many(Account, db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(UserSearchResponse {
+            status: "success".to_string(),
+            users: found
+                .into_iter()
+                .zip(accounts)
+                .map(|(user, accounts)| AdminUserData {
+                    user_id: user.user_id,
+                    email: user.email,
+                    role: user.role,
+                    kyc_status: user.kyc_status,
+                    created_at: user.created_at.to_rfc3339(),
+                    accounts: accounts.into_iter().map(AccountData::from).collect(),
+                })
+                .collect(),
+        }),
+    )))
+}
+
+#[get("/accounts/<account_id>")]
+pub async fn view_account(
+    db: &State<DatabaseConnection>,
+    staff: SupportUser,
+    account_id: &str,
+) -> Response<Json<AdminAccountResponse>> {
+    let db = db as &DatabaseConnection;
+    println!("Account {} viewed by {}", account_id, staff.0.id);
+    audit(db, &staff.0.id, "view_account", account_id).await?;
+
+    let acc = Account::find_by_id(account_id.to_string())
+        .one(db)
+        .await?
+        .ok_or_else(|| account_not_found(account_id))?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(AdminAccountResponse {
+            status: "success".to_string(),
+            user_id: acc.user_id.clone(),
+            account: acc.into(),
+        }),
+    )))
+}
+
+#[get("/transactions/<tx_id>")]
+pub async fn view_transaction(
+    db: &State<DatabaseConnection>,
+    staff: SupportUser,
+    tx_id: &str,
+) -> Response<Json<AdminTransactionResponse>> {
+    let db = db as &DatabaseConnection;
+    println!("Transaction {} viewed by {}", tx_id, staff.0.id);
+    audit(db, &staff.0.id, "view_transaction", tx_id).await?;
+
+    let (txn, acc) = Txns::find_by_id(tx_id.to_string())
+        .find_also_related(Account)
+        .one(db)
+        .await?
+        .ok_or_else(|| ErrorResponse((Status::NotFound, format!("Transaction with ID {} not found.", tx_id))))?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(AdminTransactionResponse {
+            status: "success".to_string(),
+            user_id: acc.map(|acc| acc.user_id).unwrap_or_default(),
+            transaction: txn.into(),
+        }),
+    )))
+}
+
+/// Moves `account_id` from `from` to `to` status, recording `action` by
+/// `admin_id` in the same transaction. A frozen account cannot authorize or
+/// capture holds or settle new transactions.
+async fn set_account_status(
+    db: &DatabaseConnection,
+    admin_id: &str,
+    action: &str,
+    account_id: &str,
+    from: &str,
+    to: &str,
+) -> Result<account::Model, ErrorResponse> {
+    let db_txn = db.begin().await?;
+
+    let updated = Account::update_many()
+        .col_expr(account::Column::Status, Expr::value(to))
+        .col_expr(
+            account::Column::UpdatedAt,
+            Expr::value(Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap())),
+        )
+        .filter(account::Column::AccountId.eq(account_id))
+        .filter(account::Column::Status.eq(from))
+        .exec_with_returning(&db_txn)
+        .await?;
+
+    match updated.into_iter().next() {
+        Some(acc) => {
+            audit(&db_txn, admin_id, action, account_id).await?;
+            db_txn.commit().await?;
+            Ok(acc)
+        }
+        None => {
+            let acc = Account::find_by_id(account_id.to_string())
+                .one(&db_txn)
+                .await?
+                .ok_or_else(|| account_not_found(account_id))?;
+            Err(ErrorResponse((
+                Status::Conflict,
+                format!("Account is {}, not {}.", acc.status, from),
+            )))
+        }
+    }
+}
+
+#[post("/accounts/<account_id>/freeze")]
+pub async fn freeze_account(
+    db: &State<DatabaseConnection>,
+    admin: AdminUser,
+    account_id: &str,
+) -> Response<Json<AdminAccountResponse>> {
+    let acc = set_account_status(db, &admin.0.id, "freeze_account", account_id, "active", "frozen").await?;
+    println!("Account {} frozen by {}", acc.account_id, admin.0.id);
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(AdminAccountResponse {
+            status: "success".to_string(),
+            user_id: acc.user_id.clone(),
+            account: acc.into(),
+        }),
+    )))
+}
+
+#[post("/accounts/<account_id>/unfreeze")]
+pub async fn unfreeze_account(
+    db: &State<DatabaseConnection>,
+    admin: AdminUser,
+    account_id: &str,
+) -> Response<Json<AdminAccountResponse>> {
+    let acc = set_account_status(db, &admin.0.id, "unfreeze_account", account_id, "frozen", "active").await?;
+    println!("Account {} unfrozen by {}", acc.account_id, admin.0.id);
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(AdminAccountResponse {
+            status: "success".to_string(),
+            user_id: acc.user_id.clone(),
+            account: acc.into(),
+        }),
+    )))
+}
@@ -1,25 +1,37 @@
-use std::time::SystemTime;
+use std::net::IpAddr;
+use std::sync::Arc;
 
 use crate::utils::random::generate_initial_balance;
-use crate::utils::validations::format_validation_errors_json;
+use crate::utils::validations::{format_validation_errors_json, is_valid_currency_code};
 use crate::{
     AppConfig,
-    auth::AuthenticatedUser,
+    auth::{
+        ACCESS_PURPOSE, AuthenticatedUser, Claims,
+        email,
+        keys::KeyRing,
+        mfa,
+        password::PasswordPolicy,
+        sessions::{self, Rotation},
+        throttle::{self, LoginThrottle},
+        role,
+    },
     entities::{account, prelude::*, users},
+    ledger,
+    mailer::{Email, Mailer},
 };
 
 use super::{ErrorResponse, Response, SuccessResponse};
 use bcrypt::{DEFAULT_COST, hash, verify};
 use chrono::{FixedOffset, Utc};
 use garde::Validate;
-use jsonwebtoken::{EncodingKey, Header, encode};
+use jsonwebtoken::jwk::JwkSet;
 use rocket::{
     State,
-    http::Status,
+    http::{Header as HttpHeader, Status},
     serde::{Deserialize, Serialize, json::Json},
 };
 use sea_orm_migration::prelude::Expr;
-use rand::{rng, Rng};
+use rust_decimal::Decimal;
 use sea_orm::*;
 
 #[derive(Debug, Deserialize, Validate)]
@@ -27,8 +39,14 @@ use sea_orm::*;
 pub struct ReqRegister {
     #[garde(email)]
     email: String,
-    #[garde(length(min = 12))]
+    /// Only compared with the stored hash; length rules belong to the
+    /// `PasswordPolicy` at sign-up, and changing them must not lock anyone out.
+    #[garde(skip)]
     password: String,
+    /// Label for the session this login starts, e.g. "iPhone".
+    #[garde(length(max = 100))]
+    #[serde(default)]
+    device: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -36,82 +54,258 @@ pub struct ReqRegister {
 pub struct ResRegister {
     status: String,
     token: String,
+    refresh_token: String,
+    /// Seconds until `token` expires.
+    expires_in: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Returned by a password login when the user has MFA on. `mfa_token` is
+/// exchanged with a code at `POST /auth/mfa/verify`.
+#[derive(Serialize)]
 #[serde(crate = "rocket::serde")]
-pub struct Claims {
-    sub: String,
-    role: String,
-    exp: u64,
-    token_version: i32,
+pub struct MfaChallenge {
+    status: String,
+    mfa_token: String,
+    /// Seconds until `mfa_token` expires.
+    expires_in: u64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde", untagged)]
+pub enum LoginResponse {
+    Session(ResRegister),
+    MfaRequired(MfaChallenge),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RefreshRequest {
+    refresh_token: String,
+}
+
+/// Signs a short-lived access token for `user`.
+fn access_token(keys: &KeyRing, config: &AppConfig, user: &users::Model) -> String {
+    keys.sign(
+        Claims {
+            sub: user.user_id.clone(),
+            purpose: ACCESS_PURPOSE.to_string(),
+            role: user.role.clone(),
+            token_version: user.token_version,
+        },
+        config.access_token_ttl_secs,
+    )
+}
+
+pub(super) fn token_response(
+    keys: &KeyRing,
+    config: &AppConfig,
+    user: &users::Model,
+    refresh_token: String,
+) -> ResRegister {
+    ResRegister {
+        status: "success".to_string(),
+        token: access_token(keys, config, user),
+        refresh_token,
+        expires_in: config.access_token_ttl_secs,
+    }
+}
+
+/// Login failures. A throttled caller gets 429 with a `Retry-After` header.
+#[derive(Responder)]
+pub enum LoginError {
+    Rejected(ErrorResponse),
+    #[response(status = 429)]
+    Throttled(String, HttpHeader<'static>),
+}
+
+impl From<ErrorResponse> for LoginError {
+    fn from(err: ErrorResponse) -> Self {
+        LoginError::Rejected(err)
+    }
+}
+
+impl From<DbErr> for LoginError {
+    fn from(err: DbErr) -> Self {
+        LoginError::Rejected(err.into())
+    }
+}
+
+pub(super) fn too_many_attempts(retry_after: u64) -> LoginError {
+    LoginError::Throttled(
+        "Too many failed login attempts. Try again later.".to_string(),
+        HttpHeader::new("Retry-After", retry_after.to_string()),
+    )
 }
 
 #[post("/login", data = "<req_login>")]
 pub async fn login(
     db: &State<DatabaseConnection>,
     config: &State<AppConfig>,
+    keys: &State<KeyRing>,
+    throttle: &State<LoginThrottle>,
+    ip: Option<IpAddr>,
     req_login: Json<ReqRegister>,
-) -> Response<Json<ResRegister>> {
+) -> Result<SuccessResponse<Json<LoginResponse>>, LoginError> {
     let db = db as &DatabaseConnection;
 
     if let Err(report) = req_login.validate() {
         return Err(ErrorResponse((
             Status::UnprocessableEntity,
             format_validation_errors_json(report).to_string(),
-        )));
+        ))
+        .into());
     }
 
     let config = config as &AppConfig;
 
-    let u = match Users::find()
+    // Checked before the user lookup and bcrypt so a blocked caller costs
+    // almost nothing.
+    let ip = ip.map(|ip| ip.to_string());
+    let subjects = throttle.subjects(&req_login.email, ip.clone());
+    if let Some(retry_after) = throttle.retry_after(&subjects).await {
+        return Err(too_many_attempts(retry_after));
+    }
+
+    let user = Users::find()
         .filter(users::Column::Email.eq(&req_login.email))
         .one(db)
-        .await?
-    {
-        Some(u) => u,
-        None => {
+        .await?;
+
+    let u = match user {
+        Some(u) if verify(&req_login.password, &u.password_hash).unwrap() => u,
+        _ => {
+            let locked = throttle.fail(&subjects).await;
+            if !locked.is_empty() {
+                throttle::record_lockouts(db, &req_login.email, ip.as_deref(), locked).await?;
+            }
             return Err(ErrorResponse((
                 Status::Unauthorized,
                 "Invalid Credentials".to_string(),
-            )));
+            ))
+            .into());
         }
     };
 
-    if !verify(&req_login.password, &u.password_hash).unwrap() {
+    if !u.email_verified {
         return Err(ErrorResponse((
-            Status::Unauthorized,
-            "Invalid Credentials".to_string(),
+            Status::Forbidden,
+            "Verify your email address before logging in.".to_string(),
+        ))
+        .into());
+    }
+
+    // The email's failure count is only reset once the second factor is
+    // in too, so knowing the password does not buy unlimited code guesses.
+    if u.mfa_enabled {
+        return Ok(SuccessResponse((
+            Status::Ok,
+            Json(LoginResponse::MfaRequired(MfaChallenge {
+                status: "mfa_required".to_string(),
+                mfa_token: mfa::pending_token(keys, config, &u, req_login.device.clone()),
+                expires_in: config.mfa_pending_ttl_secs,
+            })),
         )));
     }
 
-    let new_token_version = rng().random_range(0..100_000);
+    throttle.succeed(&subjects).await;
+
+    // Every login is its own session, so signing in on one device leaves
+    // the others signed in.
+    let (refresh_token, _) = sessions::issue(
+        db,
+        &u.user_id,
+        None,
+        req_login.device.clone(),
+        config.refresh_token_ttl_secs,
+    )
+    .await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(LoginResponse::Session(token_response(keys, config, &u, refresh_token))),
+    )))
+}
+
+fn invalid_refresh_token() -> ErrorResponse {
+    ErrorResponse((Status::Unauthorized, "Invalid refresh token".to_string()))
+}
+
+/// Trades a refresh token for a new access token and a new refresh token.
+/// Each refresh token works once; replaying a spent one ends its session.
+#[post("/refresh", data = "<refresh_req>")]
+pub async fn refresh(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    keys: &State<KeyRing>,
+    refresh_req: Json<RefreshRequest>,
+) -> Response<Json<ResRegister>> {
+    let db = db as &DatabaseConnection;
+
+    let (user_id, refresh_token) =
+        match sessions::rotate(db, &refresh_req.refresh_token, config.refresh_token_ttl_secs).await? {
+            Rotation::Rotated { user_id, token } => (user_id, token),
+            Rotation::Invalid => return Err(invalid_refresh_token()),
+            Rotation::Reused => {
+                return Err(ErrorResponse((
+                    Status::Unauthorized,
+                    "Refresh token was already used; the session has been revoked".to_string(),
+                )));
+            }
+        };
+
+    let u = Users::find_by_id(user_id)
+        .one(db)
+        .await?
+        .ok_or_else(invalid_refresh_token)?;
+
+    Ok(SuccessResponse((Status::Ok, Json(token_response(keys, config, &u, refresh_token)))))
+}
+
+/// Ends the session `refresh_token` belongs to. The access token stays
+/// valid until it expires.
+#[post("/logout", data = "<refresh_req>")]
+pub async fn logout(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    refresh_req: Json<RefreshRequest>,
+) -> Response<Json<JsonValue>> {
+    let db = db as &DatabaseConnection;
+
+    match sessions::find_active(db, &refresh_req.refresh_token).await? {
+        Some(token) if token.user_id == user.id => sessions::revoke_family(db, &token.family_id).await?,
+        _ => return Err(invalid_refresh_token()),
+    }
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(serde_json::json!({ "status": "success", "message": "Logged out." })),
+    )))
+}
+
+/// Ends every session and invalidates every access token the user holds.
+#[post("/logout-all")]
+pub async fn logout_all(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+) -> Response<Json<JsonValue>> {
+    let db_txn = db.begin().await?;
 
     users::Entity::update_many()
-        .col_expr(users::Column::TokenVersion, Expr::value(new_token_version))
-        .filter(users::Column::UserId.eq(&u.user_id))
-        .exec(db)
+        .col_expr(
+            users::Column::TokenVersion,
+            Expr::col(users::Column::TokenVersion).add(1),
+        )
+        .filter(users::Column::UserId.eq(&user.id))
+        .exec(&db_txn)
         .await?;
-    
-    let claims = Claims {
-        sub: u.user_id,
-        role: "users".to_string(),
-        exp: SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            + 4 * 60 * 60,
-        token_version: new_token_version,
-    };
+    sessions::revoke_all(&db_txn, &user.id).await?;
 
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(config.jwt_secret.as_bytes()),
-    )
-    .unwrap();
+    db_txn.commit().await?;
 
-    Ok(SuccessResponse((Status::Ok, Json(ResRegister { status: "success".to_string(), token }))))
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(serde_json::json!({ "status": "success", "message": "Logged out of all sessions." })),
+    )))
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -119,10 +313,19 @@ pub async fn login(
 pub struct ReqSignUp {
     #[garde(email)]
     email: String,
-    #[garde(length(min = 12))]
+    /// Checked against the `PasswordPolicy` instead.
+    #[garde(skip)]
     password: String,
     #[garde(skip)]
     profile: JsonValue,
+    /// Currency of the account opened at sign-up.
+    #[garde(custom(is_valid_currency_code))]
+    #[serde(default = "default_currency_code")]
+    currency_code: String,
+}
+
+fn default_currency_code() -> String {
+    "INR".to_string()
 }
 
 #[derive(Serialize)]
@@ -135,6 +338,10 @@ pub struct RegisterResponse {
 #[post("/register", data = "<req_register>")]
 pub async fn register(
     db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    keys: &State<KeyRing>,
+    mailer: &State<Arc<dyn Mailer>>,
+    policy: &State<PasswordPolicy>,
     req_register: Json<ReqSignUp>,
 ) -> Response<Json<RegisterResponse>> {
     let db = db as &DatabaseConnection;
@@ -145,7 +352,10 @@ pub async fn register(
             format_validation_errors_json(report).to_string(),
         )));
     }
-    let initial_balance = generate_initial_balance();
+    policy
+        .check("password", &req_register.password, &req_register.email)
+        .map_err(|errors| ErrorResponse((Status::UnprocessableEntity, errors.to_string())))?;
+    let initial_balance = generate_initial_balance(&req_register.currency_code);
 
     if Users::find()
         .filter(users::Column::Email.eq(&req_register.email))
@@ -159,45 +369,312 @@ pub async fn register(
         )));
     }
 
-    Users::insert(users::ActiveModel {
+    let db_txn = db.begin().await?;
+
+    let user = users::ActiveModel {
         email: Set(req_register.email.to_owned()),
         password_hash: Set(hash(&req_register.password, DEFAULT_COST).unwrap()),
         profile_data: Set(req_register.profile.clone()),
         created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap())),
         kyc_status: Set("pending".to_owned()),
+        role: Set(role::USER.to_string()),
         ..Default::default()
-    })
-    .exec(db)
+    }
+    .insert(&db_txn)
     .await?;
 
-    let user = Users::find()
-        .filter(users::Column::Email.eq(req_register.email.to_owned()))
-        .one(db)
-        .await?
-        .expect("User not found after insertion");
-
-    let user_id = user.user_id;
-
-    Account::insert(account::ActiveModel {
-        user_id: Set(user_id),
-        currency_code: Set("INR".to_owned()),
+    let account = account::ActiveModel {
+        user_id: Set(user.user_id.clone()),
+        currency_code: Set(req_register.currency_code.clone()),
         balance: Set(initial_balance),
-        locked_balance: Set(0.0),
+        locked_balance: Set(Decimal::ZERO),
         updated_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap())),
         ..Default::default()
-    })
-    .exec(db)
+    }
+    .insert(&db_txn)
+    .await?;
+
+    ledger::post(
+        &db_txn,
+        None,
+        ledger::clearing_account(&account.currency_code),
+        account.account_id,
+        initial_balance,
+        &account.currency_code,
+    )
     .await?;
 
+    db_txn.commit().await?;
+
+    // A lost email is not fatal: the user can ask for another one.
+    send_mail(mailer, email::verification_email(config, keys, &user)).await;
+
     Ok(SuccessResponse((
         Status::Created,
         Json(RegisterResponse {
             status: "success".to_string(),
-            message: "Account created".to_string(),
+            message: "Account created. Check your email to verify your address.".to_string(),
         }),
     )))
 }
 
+async fn send_mail(mailer: &Arc<dyn Mailer>, mail: Email) {
+    let to = mail.to.clone();
+    if let Err(e) = mailer.send(mail).await {
+        eprintln!("Failed to send mail to {}: {}", to, e);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct VerifyEmailRequest {
+    token: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(crate = "rocket::serde")]
+pub struct EmailRequest {
+    #[garde(email)]
+    email: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResetPasswordRequest {
+    token: String,
+    password: String,
+}
+
+fn message(text: &str) -> Json<JsonValue> {
+    Json(serde_json::json!({ "status": "success", "message": text }))
+}
+
+#[post("/verify-email", data = "<verify_req>")]
+pub async fn verify_email(
+    db: &State<DatabaseConnection>,
+    keys: &State<KeyRing>,
+    verify_req: Json<VerifyEmailRequest>,
+) -> Response<Json<JsonValue>> {
+    let claims = email::decode_token(keys, &verify_req.token, email::VERIFY_EMAIL).ok_or_else(|| {
+        ErrorResponse((Status::BadRequest, "Invalid or expired verification token".to_string()))
+    })?;
+
+    // Matching on the email too means a link sent before an address change
+    // cannot verify the new address.
+    let updated = Users::update_many()
+        .col_expr(users::Column::EmailVerified, Expr::value(true))
+        .filter(users::Column::UserId.eq(&claims.sub))
+        .filter(users::Column::Email.eq(&claims.email))
+        .exec(db as &DatabaseConnection)
+        .await?;
+    if updated.rows_affected != 1 {
+        return Err(ErrorResponse((
+            Status::BadRequest,
+            "Invalid or expired verification token".to_string(),
+        )));
+    }
+
+    Ok(SuccessResponse((Status::Ok, message("Email address verified."))))
+}
+
+/// Sends a new verification link. The response is the same whether or not
+/// the address belongs to an unverified user, so it cannot be used to find
+/// out who has an account.
+#[post("/resend-verification", data = "<email_req>")]
+pub async fn resend_verification(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    keys: &State<KeyRing>,
+    mailer: &State<Arc<dyn Mailer>>,
+    email_req: Json<EmailRequest>,
+) -> Response<Json<JsonValue>> {
+    if let Err(report) = email_req.validate() {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            format_validation_errors_json(report).to_string(),
+        )));
+    }
+
+    if let Some(u) = Users::find()
+        .filter(users::Column::Email.eq(&email_req.email))
+        .filter(users::Column::EmailVerified.eq(false))
+        .one(db as &DatabaseConnection)
+        .await?
+    {
+        send_mail(mailer, email::verification_email(config, keys, &u)).await;
+    }
+
+    Ok(SuccessResponse((
+        Status::Accepted,
+        message("If that address needs verifying, a new link is on its way."),
+    )))
+}
+
+/// Mails a password reset link. Like `resend_verification`, it answers the
+/// same way for unknown addresses.
+#[post("/forgot-password", data = "<email_req>")]
+pub async fn forgot_password(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    keys: &State<KeyRing>,
+    mailer: &State<Arc<dyn Mailer>>,
+    email_req: Json<EmailRequest>,
+) -> Response<Json<JsonValue>> {
+    if let Err(report) = email_req.validate() {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            format_validation_errors_json(report).to_string(),
+        )));
+    }
+
+    if let Some(u) = Users::find()
+        .filter(users::Column::Email.eq(&email_req.email))
+        .one(db as &DatabaseConnection)
+        .await?
+    {
+        send_mail(mailer, email::reset_email(config, keys, &u)).await;
+    }
+
+    Ok(SuccessResponse((
+        Status::Accepted,
+        message("If that address has an account, a reset link is on its way."),
+    )))
+}
+
+/// Sets a new password from a reset link. Every session and access token
+/// the user had is ended, and the link stops working.
+#[post("/reset-password", data = "<reset_req>")]
+pub async fn reset_password(
+    db: &State<DatabaseConnection>,
+    keys: &State<KeyRing>,
+    policy: &State<PasswordPolicy>,
+    reset_req: Json<ResetPasswordRequest>,
+) -> Response<Json<JsonValue>> {
+    let invalid_token = || ErrorResponse((Status::BadRequest, "Invalid or expired reset token".to_string()));
+    let claims = email::decode_token(keys, &reset_req.token, email::RESET_PASSWORD).ok_or_else(invalid_token)?;
+
+    policy
+        .check("password", &reset_req.password, &claims.email)
+        .map_err(|errors| ErrorResponse((Status::UnprocessableEntity, errors.to_string())))?;
+
+    let db_txn = db.begin().await?;
+
+    // Bumping token_version only if it still matches the token makes the
+    // link single-use, even when two resets race.
+    let updated = Users::update_many()
+        .col_expr(
+            users::Column::PasswordHash,
+            Expr::value(hash(&reset_req.password, DEFAULT_COST).unwrap()),
+        )
+        .col_expr(
+            users::Column::TokenVersion,
+            Expr::col(users::Column::TokenVersion).add(1),
+        )
+        // Following the link proves the user owns the address.
+        .col_expr(users::Column::EmailVerified, Expr::value(true))
+        .filter(users::Column::UserId.eq(&claims.sub))
+        .filter(users::Column::Email.eq(&claims.email))
+        .filter(users::Column::TokenVersion.eq(claims.token_version))
+        .exec(&db_txn)
+        .await?;
+    if updated.rows_affected != 1 {
+        return Err(invalid_token());
+    }
+    sessions::revoke_all(&db_txn, &claims.sub).await?;
+
+    db_txn.commit().await?;
+
+    Ok(SuccessResponse((Status::Ok, message("Password reset. Log in with the new password."))))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ChangePasswordRequest {
+    current_password: String,
+    new_password: String,
+}
+
+/// Changes the caller's password. Every other session and access token is
+/// ended; the caller gets a fresh session in the response. A wrong current
+/// password counts as a failed login.
+#[put("/password", data = "<change_req>")]
+pub async fn change_password(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    keys: &State<KeyRing>,
+    throttle: &State<LoginThrottle>,
+    policy: &State<PasswordPolicy>,
+    user: AuthenticatedUser,
+    change_req: Json<ChangePasswordRequest>,
+) -> Result<SuccessResponse<Json<ResRegister>>, LoginError> {
+    let db = db as &DatabaseConnection;
+    let config = config as &AppConfig;
+
+    let u = Users::find_by_id(user.id.clone())
+        .one(db)
+        .await?
+        .ok_or_else(|| ErrorResponse((Status::Unauthorized, "User not found".to_string())))?;
+
+    // Only the email is counted: the caller is already signed in, so the
+    // guesses all come from one account.
+    let subjects = throttle.subjects(&u.email, None);
+    if let Some(retry_after) = throttle.retry_after(&subjects).await {
+        return Err(too_many_attempts(retry_after));
+    }
+    if !verify(&change_req.current_password, &u.password_hash).unwrap() {
+        let locked = throttle.fail(&subjects).await;
+        if !locked.is_empty() {
+            throttle::record_lockouts(db, &u.email, None, locked).await?;
+        }
+        return Err(ErrorResponse((
+            Status::Forbidden,
+            "Current password is incorrect".to_string(),
+        ))
+        .into());
+    }
+    throttle.succeed(&subjects).await;
+
+    if change_req.new_password == change_req.current_password {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            serde_json::json!({ "errors": [{
+                "field": "new_password",
+                "message": "must differ from the current password",
+            }] })
+            .to_string(),
+        ))
+        .into());
+    }
+    policy
+        .check("new_password", &change_req.new_password, &u.email)
+        .map_err(|errors| ErrorResponse((Status::UnprocessableEntity, errors.to_string())))?;
+
+    let db_txn = db.begin().await?;
+
+    let u = Users::update_many()
+        .col_expr(
+            users::Column::PasswordHash,
+            Expr::value(hash(&change_req.new_password, DEFAULT_COST).unwrap()),
+        )
+        .col_expr(
+            users::Column::TokenVersion,
+            Expr::col(users::Column::TokenVersion).add(1),
+        )
+        .filter(users::Column::UserId.eq(&u.user_id))
+        .exec_with_returning(&db_txn)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| ErrorResponse((Status::Unauthorized, "User not found".to_string())))?;
+    sessions::revoke_all(&db_txn, &u.user_id).await?;
+    let (refresh_token, _) =
+        sessions::issue(&db_txn, &u.user_id, None, None, config.refresh_token_ttl_secs).await?;
+
+    db_txn.commit().await?;
+
+    Ok(SuccessResponse((Status::Ok, Json(token_response(keys, config, &u, refresh_token)))))
+}
+
 #[get("/me")]
 pub async fn me(_db: &State<DatabaseConnection>, user: AuthenticatedUser) -> Response<String> {
     Ok(SuccessResponse((
@@ -205,3 +682,10 @@ pub async fn me(_db: &State<DatabaseConnection>, user: AuthenticatedUser) -> Res
         "User ID :".to_string() + user.id.to_string().as_str(),
     )))
 }
+
+/// Public keys that verify this service's tokens, for other services.
+/// Mounted at the root as `/.well-known/jwks.json`.
+#[get("/.well-known/jwks.json")]
+pub fn jwks(keys: &State<KeyRing>) -> Json<JwkSet> {
+    Json(keys.jwks().clone())
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use super::Response;
+use super::{ErrorResponse, SuccessResponse};
+use crate::fx::FxRateProvider;
+use crate::utils::validations::{format_validation_errors_json, is_valid_currency_code};
+use crate::{AppConfig, auth::AuthenticatedUser, entities::fx_quotes};
+use chrono::{FixedOffset, Utc};
+use garde::Validate;
+use rocket::{
+    State,
+    http::Status,
+    serde::{Deserialize, Serialize, json::Json},
+};
+use rust_decimal::Decimal;
+use sea_orm::*;
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(crate = "rocket::serde")]
+pub struct QuoteRequest {
+    #[garde(custom(is_valid_currency_code))]
+    from_currency: String,
+    #[garde(custom(is_valid_currency_code))]
+    to_currency: String,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct QuoteData {
+    quote_id: String,
+    from_currency: String,
+    to_currency: String,
+    #[serde(with = "rust_decimal::serde::str")]
+    rate: Decimal,
+    expires_at: String,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct QuoteResponse {
+    status: String,
+    message: String,
+    quote: QuoteData,
+}
+
+#[post("/quotes", data = "<quote_req>")]
+pub async fn create_quote(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    rates: &State<Arc<dyn FxRateProvider>>,
+    user: AuthenticatedUser,
+    quote_req: Json<QuoteRequest>,
+) -> Response<Json<QuoteResponse>> {
+    if let Err(report) = quote_req.validate() {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            format_validation_errors_json(report).to_string(),
+        )));
+    }
+
+    if quote_req.from_currency == quote_req.to_currency {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            "from_currency and to_currency must differ.".to_string(),
+        )));
+    }
+
+    let rate = match rates.rate(&quote_req.from_currency, &quote_req.to_currency).await {
+        Ok(Some(rate)) => rate,
+        Ok(None) => {
+            return Err(ErrorResponse((
+                Status::UnprocessableEntity,
+                format!(
+                    "No rate available for {} to {}.",
+                    quote_req.from_currency, quote_req.to_currency
+                ),
+            )));
+        }
+        Err(e) => {
+            eprintln!("FX rate lookup failed: {}", e);
+            return Err(ErrorResponse((
+                Status::ServiceUnavailable,
+                "FX rates are temporarily unavailable.".to_string(),
+            )));
+        }
+    };
+
+    let now = Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap());
+
+    let quote = fx_quotes::ActiveModel {
+        user_id: Set(user.id),
+        from_currency: Set(quote_req.from_currency.clone()),
+        to_currency: Set(quote_req.to_currency.clone()),
+        rate: Set(rate),
+        expires_at: Set(now + chrono::Duration::seconds(config.fx_quote_ttl_secs)),
+        created_at: Set(now),
+        ..Default::default()
+    }
+    .insert(db.inner())
+    .await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(QuoteResponse {
+            status: "success".to_string(),
+            message: "Rate locked until expires_at.".to_string(),
+            quote: QuoteData {
+                quote_id: quote.quote_id,
+                from_currency: quote.from_currency,
+                to_currency: quote.to_currency,
+                rate: quote.rate,
+                expires_at: quote.expires_at.to_rfc3339(),
+            },
+        }),
+    )))
+}
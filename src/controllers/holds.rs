@@ -0,0 +1,426 @@
+use super::Response;
+use super::accounts::{single_account, user_accounts};
+use super::{ErrorResponse, SuccessResponse};
+use crate::holds;
+use crate::idempotency::{self, IdempotencyKey};
+use crate::ledger;
+use crate::processing::decision::reason;
+use crate::txn_status::{self, Transition, TxnStatus};
+use crate::utils::money::fits_currency_scale;
+use crate::utils::validations::{
+    TxnTypeContext, format_validation_errors_json, is_valid_amount, is_valid_refund_amount,
+};
+use crate::{
+    AppConfig,
+    auth::{AuthenticatedUser, mfa::StepUp},
+    entities::holds::{ActiveModel as HoldActiveModel, Model as HoldModel},
+    entities::{account, prelude::*, txns},
+};
+use chrono::{FixedOffset, Utc};
+use garde::Validate;
+use rocket::{
+    State,
+    http::Status,
+    serde::{Deserialize, Serialize, json::Json},
+};
+use rust_decimal::Decimal;
+use sea_orm::sea_query::LockType;
+use sea_orm::*;
+use serde_json::json;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct HoldData {
+    hold_id: String,
+    account_id: String,
+    #[serde(with = "rust_decimal::serde::str")]
+    amount: Decimal,
+    currency_code: String,
+    status: String,
+    capture_txn_id: Option<String>,
+    expires_at: String,
+    created_at: String,
+}
+
+impl From<HoldModel> for HoldData {
+    fn from(hold: HoldModel) -> Self {
+        HoldData {
+            hold_id: hold.hold_id,
+            account_id: hold.account_id,
+            amount: hold.amount,
+            currency_code: hold.currency_code,
+            status: hold.status,
+            capture_txn_id: hold.capture_txn_id,
+            expires_at: hold.expires_at.to_rfc3339(),
+            created_at: hold.created_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct HoldResponse {
+    status: String,
+    message: String,
+    hold: HoldData,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(crate = "rocket::serde")]
+#[garde(context(TxnTypeContext))]
+pub struct AuthorizeRequest {
+    #[garde(custom(is_valid_amount))]
+    #[serde(with = "rust_decimal::serde::str")]
+    amount: Decimal,
+    #[garde(skip)]
+    account_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(crate = "rocket::serde")]
+#[garde(context(TxnTypeContext))]
+pub struct CaptureRequest {
+    /// Amount to capture; omit it to capture the whole hold.
+    #[garde(custom(is_valid_refund_amount))]
+    #[serde(default, with = "rust_decimal::serde::str_option")]
+    amount: Option<Decimal>,
+}
+
+fn declined(reason_code: &str, message: String) -> ErrorResponse {
+    ErrorResponse((
+        Status::UnprocessableEntity,
+        json!({ "status": "declined", "reason": reason_code, "message": message }).to_string(),
+    ))
+}
+
+#[post("/authorize", data = "<auth_req>")]
+pub async fn authorize(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    idempotency_key: IdempotencyKey,
+    step_up: StepUp<'_>,
+    auth_req: Json<AuthorizeRequest>,
+) -> Response<Json<HoldResponse>> {
+    let db = db.inner();
+
+    if let Err(report) = auth_req.validate() {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            format_validation_errors_json(report).to_string(),
+        )));
+    }
+
+    let request_hash = idempotency::fingerprint(&json!({
+        "authorize": auth_req.amount.normalize().to_string(),
+        "account_id": auth_req.account_id,
+    }));
+
+    if let Some(key) = &idempotency_key.0
+        && let Some((status, replayed)) = idempotency::replay(db, &user.id, key, &request_hash).await?
+    {
+        return Ok(SuccessResponse((status, Json(replayed))));
+    }
+
+    let db_txn = db.begin().await?;
+
+    // Lock the account so the available balance cannot change between the
+    // check and the reservation.
+    let account = single_account(
+        user_accounts(&user.id, auth_req.account_id.as_deref())
+            .limit(2)
+            .lock_exclusive()
+            .all(&db_txn)
+            .await?,
+    )?;
+
+    if !fits_currency_scale(&auth_req.amount, &account.currency_code) {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            format!(
+                "amount has more decimal places than {} allows.",
+                account.currency_code
+            ),
+        )));
+    }
+
+    // Checked outside `db_txn` so a throttle lockout is kept when the
+    // reservation is abandoned.
+    step_up
+        .require(db, &user.id, auth_req.amount, &account.currency_code)
+        .await?;
+
+    if account.status != "active" {
+        return Err(declined(
+            reason::ACCOUNT_INACTIVE,
+            format!("Account {} is {}", account.account_id, account.status),
+        ));
+    }
+    if auth_req.amount > config.max_txn_amount {
+        return Err(declined(
+            reason::AMOUNT_OVER_LIMIT,
+            format!("Amount exceeds the per-transaction limit of {}", config.max_txn_amount),
+        ));
+    }
+    if account.balance - account.locked_balance < auth_req.amount {
+        return Err(declined(
+            reason::INSUFFICIENT_FUNDS,
+            "Insufficient available balance".to_string(),
+        ));
+    }
+
+    let now = Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap());
+
+    let hold = HoldActiveModel {
+        account_id: Set(account.account_id.clone()),
+        amount: Set(auth_req.amount),
+        currency_code: Set(account.currency_code.clone()),
+        status: Set(holds::ACTIVE.to_string()),
+        expires_at: Set(now + chrono::Duration::seconds(config.hold_ttl_secs)),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    }
+    .insert(&db_txn)
+    .await?;
+
+    let locked_balance = account.locked_balance + auth_req.amount;
+    let mut account_am = account.into_active_model();
+    account_am.locked_balance = Set(locked_balance);
+    account_am.updated_at = Set(now);
+    account_am.update(&db_txn).await?;
+
+    let response = HoldResponse {
+        status: "success".to_string(),
+        message: "Funds authorized and held.".to_string(),
+        hold: hold.into(),
+    };
+
+    if let Some(key) = &idempotency_key.0
+        && let Err(err) =
+            idempotency::store(&db_txn, &user.id, key, &request_hash, Status::Created, &response).await
+    {
+        db_txn.rollback().await?;
+        if let Some(SqlErr::UniqueConstraintViolation(_)) = err.sql_err()
+            && let Some((status, replayed)) =
+                idempotency::replay(db, &user.id, key, &request_hash).await?
+        {
+            return Ok(SuccessResponse((status, Json(replayed))));
+        }
+        return Err(err.into());
+    }
+
+    db_txn.commit().await?;
+
+    Ok(SuccessResponse((Status::Created, Json(response))))
+}
+
+/// Locks `hold_id` and its account for the rest of `db_txn` and checks that
+/// the hold belongs to `user` and can still be captured or voided.
+async fn lock_active_hold(
+    db_txn: &DatabaseTransaction,
+    user: &AuthenticatedUser,
+    hold_id: &str,
+) -> Result<(HoldModel, account::Model), ErrorResponse> {
+    let not_found = || ErrorResponse((Status::NotFound, format!("Hold with ID {} not found.", hold_id)));
+
+    // The owner is filtered in the locking query, so another user's hold is
+    // never locked; `OF holds` leaves the account to the lock below.
+    let mut owned_hold = Holds::find_by_id(hold_id.to_string())
+        .inner_join(Account)
+        .filter(account::Column::UserId.eq(user.id.clone()));
+    QueryTrait::query(&mut owned_hold).lock_with_tables(LockType::Update, [Holds]);
+    let hold = owned_hold.one(db_txn).await?.ok_or_else(not_found)?;
+
+    // Locked so a freeze cannot land between the status check in `capture`
+    // and the debit.
+    let account = Account::find_by_id(hold.account_id.clone())
+        .lock_exclusive()
+        .one(db_txn)
+        .await?
+        .ok_or_else(not_found)?;
+
+    if hold.status != holds::ACTIVE {
+        return Err(ErrorResponse((
+            Status::Conflict,
+            format!("Hold is already {}.", hold.status),
+        )));
+    }
+    if hold.expires_at <= Utc::now() {
+        return Err(ErrorResponse((Status::Conflict, "Hold has expired.".to_string())));
+    }
+
+    Ok((hold, account))
+}
+
+/// Stores `response` under the caller's idempotency key and commits. If a
+/// concurrent request with the same key got there first, its response is
+/// returned instead.
+async fn commit_with_key(
+    db: &DatabaseConnection,
+    db_txn: DatabaseTransaction,
+    user: &AuthenticatedUser,
+    idempotency_key: &IdempotencyKey,
+    request_hash: &str,
+    response: HoldResponse,
+) -> Response<Json<HoldResponse>> {
+    if let Some(key) = &idempotency_key.0
+        && let Err(err) =
+            idempotency::store(&db_txn, &user.id, key, request_hash, Status::Ok, &response).await
+    {
+        db_txn.rollback().await?;
+        if let Some(SqlErr::UniqueConstraintViolation(_)) = err.sql_err()
+            && let Some((status, replayed)) =
+                idempotency::replay(db, &user.id, key, request_hash).await?
+        {
+            return Ok(SuccessResponse((status, Json(replayed))));
+        }
+        return Err(err.into());
+    }
+
+    db_txn.commit().await?;
+
+    Ok(SuccessResponse((Status::Ok, Json(response))))
+}
+
+#[post("/holds/<hold_id>/capture", data = "<capture_req>")]
+pub async fn capture(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    idempotency_key: IdempotencyKey,
+    step_up: StepUp<'_>,
+    hold_id: &str,
+    capture_req: Json<CaptureRequest>,
+) -> Response<Json<HoldResponse>> {
+    let db = db.inner();
+
+    if let Err(report) = capture_req.validate() {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            format_validation_errors_json(report).to_string(),
+        )));
+    }
+
+    let request_hash = idempotency::fingerprint(&json!({
+        "capture": hold_id,
+        "amount": capture_req.amount.map(|amount| amount.normalize().to_string()),
+    }));
+
+    if let Some(key) = &idempotency_key.0
+        && let Some((status, replayed)) = idempotency::replay(db, &user.id, key, &request_hash).await?
+    {
+        return Ok(SuccessResponse((status, Json(replayed))));
+    }
+
+    let db_txn = db.begin().await?;
+    let (hold, account) = lock_active_hold(&db_txn, &user, hold_id).await?;
+
+    // A hold placed before a freeze stays reserved but cannot be captured;
+    // voiding it only releases funds, so that is still allowed.
+    if account.status != "active" {
+        return Err(declined(
+            reason::ACCOUNT_INACTIVE,
+            format!("Account {} is {}", account.account_id, account.status),
+        ));
+    }
+
+    let amount = capture_req.amount.unwrap_or(hold.amount);
+    if amount > hold.amount {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            format!("amount exceeds the held amount of {}.", hold.amount),
+        )));
+    }
+    if !fits_currency_scale(&amount, &hold.currency_code) {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            format!(
+                "amount has more decimal places than {} allows.",
+                hold.currency_code
+            ),
+        )));
+    }
+
+    // Capturing is what moves the money, so it needs step-up just like
+    // placing the hold; a stolen token must not be able to finish one.
+    // Checked outside `db_txn` so a throttle lockout is kept.
+    step_up
+        .require(db, &user.id, amount, &hold.currency_code)
+        .await?;
+
+    // The funds were reserved when the hold was placed, so the capture
+    // settles immediately instead of going through the processor.
+    let capture_txn = txns::ActiveModel {
+        account_id: Set(hold.account_id.clone()),
+        amount: Set(amount),
+        currency_code: Set(hold.currency_code.clone()),
+        txn_type: Set("capture".to_string()),
+        status: Set(TxnStatus::Succeeded),
+        created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap())),
+        ..Default::default()
+    }
+    .insert(&db_txn)
+    .await?;
+
+    txn_status::record(
+        &db_txn,
+        Transition {
+            txn_id: &capture_txn.txn_id,
+            from: None,
+            to: TxnStatus::Succeeded,
+            reason_code: None,
+            message: Some(format!("Captured from {}", hold.hold_id)),
+            actor: &txn_status::user_actor(&user.id),
+        },
+    )
+    .await?;
+
+    ledger::post(
+        &db_txn,
+        Some(capture_txn.txn_id.clone()),
+        hold.account_id.clone(),
+        ledger::clearing_account(&hold.currency_code),
+        amount,
+        &hold.currency_code,
+    )
+    .await?;
+
+    let hold = holds::close(&db_txn, hold, holds::CAPTURED, amount, Some(capture_txn.txn_id)).await?;
+
+    let response = HoldResponse {
+        status: "success".to_string(),
+        message: "Hold captured.".to_string(),
+        hold: hold.into(),
+    };
+    commit_with_key(db, db_txn, &user, &idempotency_key, &request_hash, response).await
+}
+
+#[post("/holds/<hold_id>/void")]
+pub async fn void(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    idempotency_key: IdempotencyKey,
+    hold_id: &str,
+) -> Response<Json<HoldResponse>> {
+    let db = db.inner();
+
+    let request_hash = idempotency::fingerprint(&json!({ "void": hold_id }));
+
+    if let Some(key) = &idempotency_key.0
+        && let Some((status, replayed)) = idempotency::replay(db, &user.id, key, &request_hash).await?
+    {
+        return Ok(SuccessResponse((status, Json(replayed))));
+    }
+
+    let db_txn = db.begin().await?;
+    let (hold, _) = lock_active_hold(&db_txn, &user, hold_id).await?;
+
+    let hold = holds::close(&db_txn, hold, holds::VOIDED, Decimal::ZERO, None).await?;
+
+    let response = HoldResponse {
+        status: "success".to_string(),
+        message: "Hold voided and funds released.".to_string(),
+        hold: hold.into(),
+    };
+    commit_with_key(db, db_txn, &user, &idempotency_key, &request_hash, response).await
+}
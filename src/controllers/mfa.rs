@@ -0,0 +1,296 @@
+use std::net::IpAddr;
+
+use super::auth::{LoginError, ResRegister, token_response, too_many_attempts};
+use super::{ErrorResponse, Response, SuccessResponse};
+use crate::{
+    AppConfig,
+    auth::{
+        AuthenticatedUser,
+        keys::KeyRing,
+        mfa,
+        sessions,
+        throttle::{self, LoginThrottle},
+    },
+    entities::{mfa_recovery_codes, prelude::*, users},
+};
+use rocket::{
+    State,
+    http::Status,
+    serde::{Deserialize, Serialize, json::Json},
+};
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CodeRequest {
+    code: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct VerifyRequest {
+    mfa_token: String,
+    code: String,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct MfaStatusResponse {
+    status: String,
+    enabled: bool,
+    recovery_codes_remaining: u64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct EnrollResponse {
+    status: String,
+    /// Base32 secret, for apps that cannot scan `otpauth_uri`.
+    secret: String,
+    otpauth_uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RecoveryCodesResponse {
+    status: String,
+    /// Shown once; only their hashes are kept.
+    recovery_codes: Vec<String>,
+}
+
+async fn current_user(db: &DatabaseConnection, user: &AuthenticatedUser) -> Result<users::Model, ErrorResponse> {
+    Users::find_by_id(user.id.clone())
+        .one(db)
+        .await?
+        .ok_or_else(|| ErrorResponse((Status::Unauthorized, "User not found".to_string())))
+}
+
+fn invalid_code() -> ErrorResponse {
+    ErrorResponse((Status::Unauthorized, "Invalid two-factor code".to_string()))
+}
+
+#[get("/")]
+pub async fn status(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+) -> Response<Json<MfaStatusResponse>> {
+    let db = db as &DatabaseConnection;
+    let u = current_user(db, &user).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(MfaStatusResponse {
+            status: "success".to_string(),
+            enabled: u.mfa_enabled,
+            recovery_codes_remaining: mfa::remaining_recovery_codes(db, &u.user_id).await?,
+        }),
+    )))
+}
+
+/// Starts enrollment with a new secret. MFA stays off until a code from it
+/// is confirmed at `/activate`; enrolling again replaces the secret.
+#[post("/enroll")]
+pub async fn enroll(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+) -> Response<Json<EnrollResponse>> {
+    let db = db as &DatabaseConnection;
+    let u = current_user(db, &user).await?;
+
+    if u.mfa_enabled {
+        return Err(ErrorResponse((
+            Status::Conflict,
+            "Two-factor authentication is already enabled.".to_string(),
+        )));
+    }
+
+    let secret = mfa::new_secret();
+    let otpauth_uri = mfa::otpauth_uri(&secret, &config.mfa_issuer, &u.email).ok_or_else(|| {
+        ErrorResponse((Status::InternalServerError, "Could not build the otpauth URI".to_string()))
+    })?;
+
+    Users::update_many()
+        .col_expr(users::Column::MfaSecret, Expr::value(secret.clone()))
+        .col_expr(users::Column::MfaLastStep, Expr::value(Option::<i64>::None))
+        .filter(users::Column::UserId.eq(&u.user_id))
+        .filter(users::Column::MfaEnabled.eq(false))
+        .exec(db)
+        .await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(EnrollResponse {
+            status: "success".to_string(),
+            secret,
+            otpauth_uri,
+        }),
+    )))
+}
+
+/// Turns MFA on once the caller proves their app has the secret, and
+/// returns a fresh set of recovery codes.
+#[post("/activate", data = "<code_req>")]
+pub async fn activate(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    code_req: Json<CodeRequest>,
+) -> Response<Json<RecoveryCodesResponse>> {
+    let db = db as &DatabaseConnection;
+    let u = current_user(db, &user).await?;
+
+    let secret = match (u.mfa_enabled, u.mfa_secret.as_deref()) {
+        (false, Some(secret)) => secret,
+        (true, _) => {
+            return Err(ErrorResponse((
+                Status::Conflict,
+                "Two-factor authentication is already enabled.".to_string(),
+            )));
+        }
+        (false, None) => {
+            return Err(ErrorResponse((
+                Status::Conflict,
+                "Start enrollment at /auth/mfa/enroll first.".to_string(),
+            )));
+        }
+    };
+
+    let db_txn = db.begin().await?;
+
+    if !mfa::verify_totp(&db_txn, &u, secret, code_req.code.trim()).await? {
+        return Err(invalid_code());
+    }
+    Users::update_many()
+        .col_expr(users::Column::MfaEnabled, Expr::value(true))
+        .filter(users::Column::UserId.eq(&u.user_id))
+        .exec(&db_txn)
+        .await?;
+    let recovery_codes = mfa::issue_recovery_codes(&db_txn, &u.user_id).await?;
+
+    db_txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(RecoveryCodesResponse {
+            status: "success".to_string(),
+            recovery_codes,
+        }),
+    )))
+}
+
+/// Replaces the recovery codes. Takes a current code so a stolen access
+/// token cannot mint new ones; wrong codes are throttled per user.
+#[post("/recovery-codes", data = "<code_req>")]
+pub async fn regenerate_recovery_codes(
+    db: &State<DatabaseConnection>,
+    throttle: &State<LoginThrottle>,
+    user: AuthenticatedUser,
+    code_req: Json<CodeRequest>,
+) -> Response<Json<RecoveryCodesResponse>> {
+    let db = db as &DatabaseConnection;
+    let u = current_user(db, &user).await?;
+
+    if !mfa::verify_throttled(db, throttle, &u, &code_req.code).await? {
+        return Err(invalid_code());
+    }
+
+    let db_txn = db.begin().await?;
+    let recovery_codes = mfa::issue_recovery_codes(&db_txn, &u.user_id).await?;
+    db_txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(RecoveryCodesResponse {
+            status: "success".to_string(),
+            recovery_codes,
+        }),
+    )))
+}
+
+#[post("/disable", data = "<code_req>")]
+pub async fn disable(
+    db: &State<DatabaseConnection>,
+    throttle: &State<LoginThrottle>,
+    user: AuthenticatedUser,
+    code_req: Json<CodeRequest>,
+) -> Response<Json<JsonValue>> {
+    let db = db as &DatabaseConnection;
+    let u = current_user(db, &user).await?;
+
+    if !mfa::verify_throttled(db, throttle, &u, &code_req.code).await? {
+        return Err(invalid_code());
+    }
+
+    let db_txn = db.begin().await?;
+    Users::update_many()
+        .col_expr(users::Column::MfaEnabled, Expr::value(false))
+        .col_expr(users::Column::MfaSecret, Expr::value(Option::<String>::None))
+        .col_expr(users::Column::MfaLastStep, Expr::value(Option::<i64>::None))
+        .filter(users::Column::UserId.eq(&u.user_id))
+        .exec(&db_txn)
+        .await?;
+    MfaRecoveryCodes::delete_many()
+        .filter(mfa_recovery_codes::Column::UserId.eq(&u.user_id))
+        .exec(&db_txn)
+        .await?;
+
+    db_txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(serde_json::json!({ "status": "success", "message": "Two-factor authentication disabled." })),
+    )))
+}
+
+/// Second half of a login with MFA on: trades the pending token from
+/// `/auth/login` and a code for a session. Wrong codes count as failed
+/// logins against the user's email.
+#[post("/verify", data = "<verify_req>")]
+pub async fn verify(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    keys: &State<KeyRing>,
+    throttle: &State<LoginThrottle>,
+    ip: Option<IpAddr>,
+    verify_req: Json<VerifyRequest>,
+) -> Result<SuccessResponse<Json<ResRegister>>, LoginError> {
+    let db = db as &DatabaseConnection;
+    let config = config as &AppConfig;
+
+    let invalid_token = || ErrorResponse((Status::Unauthorized, "Invalid or expired MFA token".to_string()));
+
+    let claims = mfa::decode_pending_token(keys, &verify_req.mfa_token).ok_or_else(invalid_token)?;
+    let u = Users::find_by_id(claims.sub)
+        .one(db)
+        .await?
+        .filter(|u| u.token_version == claims.token_version)
+        .ok_or_else(invalid_token)?;
+
+    let ip = ip.map(|ip| ip.to_string());
+    let subjects = throttle.subjects(&u.email, ip.clone());
+    if let Some(retry_after) = throttle.retry_after(&subjects).await {
+        return Err(too_many_attempts(retry_after));
+    }
+
+    if !mfa::verify_throttled(db, throttle, &u, &verify_req.code).await? {
+        let locked = throttle.fail(&subjects).await;
+        if !locked.is_empty() {
+            throttle::record_lockouts(db, &u.email, ip.as_deref(), locked).await?;
+        }
+        return Err(invalid_code().into());
+    }
+
+    throttle.succeed(&subjects).await;
+
+    let (refresh_token, _) = sessions::issue(
+        db,
+        &u.user_id,
+        None,
+        claims.device,
+        config.refresh_token_ttl_secs,
+    )
+    .await?;
+
+    Ok(SuccessResponse((Status::Ok, Json(token_response(keys, config, &u, refresh_token)))))
+}
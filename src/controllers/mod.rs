@@ -1,8 +1,12 @@
 use rocket::http::Status;
 use sea_orm::DbErr;
 
+pub mod admin;
 pub mod auth;
 pub mod accounts;
+pub mod fx;
+pub mod holds;
+pub mod mfa;
 pub mod profile;
 pub mod transactions;
 
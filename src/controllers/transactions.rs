@@ -1,30 +1,79 @@
 use super::Response;
 use super::{ErrorResponse, SuccessResponse};
-use crate::kafka::producer::{create_producer, produce};
+use super::accounts::{single_account, user_accounts};
+use crate::idempotency::{self, IdempotencyKey};
+use crate::kafka::TRANSACTION_EVENTS_TOPIC;
+use crate::fx;
+use crate::outbox;
+use crate::refunds;
+use crate::txn_status::{self, Transition, TxnStatus};
+use crate::utils::money::fits_currency_scale;
 use crate::utils::validations::{
-    format_validation_errors_json, is_valid_tx_id, is_valid_txn_type, TxnTypeContext,TxnViewContext
+    format_validation_errors_json, is_valid_amount, is_valid_refund_amount, is_valid_tx_id, is_valid_txn_type, TxnTypeContext,TxnViewContext
 };
-use crate::{auth::AuthenticatedUser, entities::{account, prelude::*, txns}};
-use chrono::{FixedOffset, Utc};
+use crate::{
+    auth::{AuthenticatedUser, mfa::StepUp},
+    entities::{account, fx_quotes, prelude::*, txns, users},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, FixedOffset, Utc};
 use garde::Validate;
 use rocket::{
     http::Status,
     serde::{Deserialize, Serialize, json::Json},
     State,
 };
+use rust_decimal::Decimal;
+use sea_orm::sea_query::{Expr, LockType};
 use sea_orm::*;
 use serde_json::json;
+use std::str::FromStr;
 
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct TransactionData {
     txn_id: String,
     account_id: String,
-    amount: f32,
+    #[serde(with = "rust_decimal::serde::str")]
+    amount: Decimal,
     currency_code: String,
     txn_type: String,
-    status: String,
+    status: TxnStatus,
     created_at: String,
+    parent_txn_id: Option<String>,
+    counterparty_account_id: Option<String>,
+    #[serde(with = "rust_decimal::serde::str_option")]
+    fx_rate: Option<Decimal>,
+}
+
+impl From<txns::Model> for TransactionData {
+    fn from(txn: txns::Model) -> Self {
+        TransactionData {
+            txn_id: txn.txn_id,
+            account_id: txn.account_id,
+            amount: txn.amount,
+            currency_code: txn.currency_code,
+            txn_type: txn.txn_type,
+            status: txn.status,
+            created_at: txn.created_at.to_rfc3339(),
+            parent_txn_id: txn.parent_txn_id,
+            counterparty_account_id: txn.counterparty_account_id,
+            fx_rate: txn.fx_rate,
+        }
+    }
+}
+
+/// Payload published on the transaction events topic for a new txn.
+fn event_payload(txn: &txns::Model) -> serde_json::Value {
+    json!({
+        "txn_id": txn.txn_id,
+        "account_id": txn.account_id,
+        "amount": txn.amount.to_string(),
+        "currency_code": txn.currency_code,
+        "txn_type": txn.txn_type,
+        "status": txn.status,
+        "created_at": txn.created_at.to_rfc3339(),
+    })
 }
 
 #[derive(Serialize, Deserialize)]
@@ -39,10 +88,22 @@ pub struct TransactionResponse {
 #[serde(crate = "rocket::serde")]
 #[garde(context(TxnTypeContext))]
 pub struct TransactionRequest {
-    #[garde(range(min = 0.01))]
-    amount: f32,
+    #[garde(custom(is_valid_amount))]
+    #[serde(with = "rust_decimal::serde::str")]
+    amount: Decimal,
     #[garde(custom(is_valid_txn_type))]
     txn_type: String,
+    /// Account to transact on; required once the user has several accounts.
+    #[garde(skip)]
+    account_id: Option<String>,
+    /// Recipient of a `transfer`, by account id or by the owner's email.
+    #[garde(skip)]
+    to_account_id: Option<String>,
+    #[garde(skip)]
+    to_email: Option<String>,
+    /// Locked rate from `POST /fx/quotes`; required for an `exchange`.
+    #[garde(skip)]
+    quote_id: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -56,6 +117,8 @@ pub struct TransactionErrResponse {
 pub async fn create_transaction(
     db: &State<DatabaseConnection>,
     user: AuthenticatedUser,
+    idempotency_key: IdempotencyKey,
+    step_up: StepUp<'_>,
     txn_req: Json<TransactionRequest>,
 ) -> Response<Json<TransactionResponse>> {
     let db = db.inner();
@@ -67,39 +130,125 @@ pub async fn create_transaction(
         )));
     }
 
-    let account = match Account::find()
-        .filter(account::Column::UserId.eq(user.id.clone()))
-        .one(db)
-        .await
+    let request_hash = idempotency::fingerprint(&json!({
+        "amount": txn_req.amount.normalize().to_string(),
+        "txn_type": txn_req.txn_type,
+        "account_id": txn_req.account_id,
+        "to_account_id": txn_req.to_account_id,
+        "to_email": txn_req.to_email,
+        "quote_id": txn_req.quote_id,
+    }));
+
+    if let Some(key) = &idempotency_key.0
+        && let Some((status, replayed)) = idempotency::replay(db, &user.id, key, &request_hash).await?
     {
-        Ok(Some(acc)) => acc,
-        Ok(None) => {
+        return Ok(SuccessResponse((status, Json(replayed))));
+    }
+
+    let quote = match (txn_req.txn_type.as_str(), &txn_req.quote_id) {
+        ("exchange", Some(quote_id)) => Some(usable_quote(db, &user, quote_id).await?),
+        ("exchange", None) => {
             return Err(ErrorResponse((
-                Status::NotFound,
-                "Account not found for the user.".to_string(),
+                Status::UnprocessableEntity,
+                "An exchange needs a quote_id.".to_string(),
             )));
         }
-        Err(_) => {
+        (_, Some(_)) => {
             return Err(ErrorResponse((
-                Status::InternalServerError,
-                "DB error while fetching account.".to_string(),
+                Status::UnprocessableEntity,
+                "A quote_id can only be given for an exchange.".to_string(),
             )));
         }
+        _ => None,
+    };
+
+    // An exchange debits the account in the quote's source currency, so the
+    // quote picks the account when the user has several.
+    let mut accounts = user_accounts(&user.id, txn_req.account_id.as_deref());
+    if let Some(quote) = &quote {
+        accounts = accounts.filter(account::Column::CurrencyCode.eq(quote.from_currency.clone()));
+    }
+    let account = single_account(accounts.limit(2).all(db).await?)?;
+
+    if !fits_currency_scale(&txn_req.amount, &account.currency_code) {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            format!(
+                "amount has more decimal places than {} allows.",
+                account.currency_code
+            ),
+        )));
+    }
+
+    // A credit brings money in, so only the other types need step-up.
+    if txn_req.txn_type != "credit" {
+        step_up
+            .require(db, &user.id, txn_req.amount, &account.currency_code)
+            .await?;
+    }
+
+    let counterparty_account_id = if txn_req.txn_type == "transfer" {
+        Some(resolve_recipient(db, &txn_req, &account).await?.account_id)
+    } else if let Some(quote) = &quote {
+        let target = user_accounts(&user.id, None)
+            .filter(account::Column::CurrencyCode.eq(quote.to_currency.clone()))
+            .one(db)
+            .await?
+            .ok_or_else(|| {
+                ErrorResponse((
+                    Status::NotFound,
+                    format!("You have no {} account to exchange into.", quote.to_currency),
+                ))
+            })?;
+        if fx::convert(txn_req.amount, quote.rate, &quote.to_currency).is_zero() {
+            return Err(ErrorResponse((
+                Status::UnprocessableEntity,
+                format!("amount is too small to buy any {}.", quote.to_currency),
+            )));
+        }
+        Some(target.account_id)
+    } else if txn_req.to_account_id.is_some() || txn_req.to_email.is_some() {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            "A recipient can only be given for a transfer.".to_string(),
+        )));
+    } else {
+        None
     };
 
     let now = Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap());
 
+    let db_txn = db.begin().await?;
+
     let new_txn = txns::ActiveModel {
         account_id: Set(account.account_id.clone()),
         amount: Set(txn_req.amount),
         currency_code: Set(account.currency_code.clone()),
         txn_type: Set(txn_req.txn_type.clone()),
-        status: Set("pending".to_string()),
+        status: Set(TxnStatus::Pending),
         created_at: Set(now),
+        counterparty_account_id: Set(counterparty_account_id),
+        fx_rate: Set(quote.as_ref().map(|quote| quote.rate)),
         ..Default::default() // txn_id will be generated by DB
     };
 
-    let inserted_txn = match new_txn.insert(db).await {
+    // Quotes are single use: claim it in the same transaction so a failed
+    // create leaves it usable and two creates cannot both spend it.
+    if let Some(quote) = &quote {
+        let claimed = FxQuotes::update_many()
+            .col_expr(fx_quotes::Column::UsedAt, Expr::value(now))
+            .filter(fx_quotes::Column::QuoteId.eq(quote.quote_id.clone()))
+            .filter(fx_quotes::Column::UsedAt.is_null())
+            .filter(fx_quotes::Column::ExpiresAt.gt(now))
+            .exec(&db_txn)
+            .await?
+            .rows_affected;
+        if claimed == 0 {
+            return Err(quote_unusable());
+        }
+    }
+
+    let inserted_txn = match new_txn.insert(&db_txn).await {
         Ok(model) => model,
         Err(_) => {
             return Err(ErrorResponse((
@@ -109,37 +258,272 @@ pub async fn create_transaction(
         }
     };
 
-    let txn_data = json!({
-        "txn_id": inserted_txn.txn_id,
-        "account_id": inserted_txn.account_id,
-        "amount": inserted_txn.amount,
-        "currency_code": inserted_txn.currency_code,
-        "txn_type": inserted_txn.txn_type,
-        "status": inserted_txn.status,
-        "created_at": inserted_txn.created_at.to_rfc3339(),
-    });
+    let txn_data = event_payload(&inserted_txn);
 
-    let kafka_producer = create_producer();
-    produce(kafka_producer, txn_data.to_string()).await;
+    let response = TransactionResponse {
+        status: "pending".to_string(),
+        message: "Transaction created and queued for processing.".to_string(),
+        transaction: inserted_txn.into(),
+    };
 
-    Ok(SuccessResponse((
-        Status::Accepted,
-        Json(TransactionResponse {
-            status: "pending".to_string(),
-            message: "Transaction created and queued for processing.".to_string(),
-            transaction: TransactionData {
-                txn_id: inserted_txn.txn_id,
-                account_id: inserted_txn.account_id,
-                amount: inserted_txn.amount,
-                currency_code: inserted_txn.currency_code,
-                txn_type: inserted_txn.txn_type,
-                status: inserted_txn.status,
-                created_at: inserted_txn.created_at.to_rfc3339(),
-            },
-        }),
-    )))
+    if let Some(key) = &idempotency_key.0
+        && let Err(err) =
+            idempotency::store(&db_txn, &user.id, key, &request_hash, Status::Accepted, &response).await
+    {
+        db_txn.rollback().await?;
+        // Lost a race with a concurrent request carrying the same key.
+        if let Some(SqlErr::UniqueConstraintViolation(_)) = err.sql_err()
+            && let Some((status, replayed)) =
+                idempotency::replay(db, &user.id, key, &request_hash).await?
+        {
+            return Ok(SuccessResponse((status, Json(replayed))));
+        }
+        return Err(err.into());
+    }
+
+    txn_status::record(
+        &db_txn,
+        Transition {
+            txn_id: &response.transaction.txn_id,
+            from: None,
+            to: response.transaction.status,
+            reason_code: None,
+            message: None,
+            actor: &txn_status::user_actor(&user.id),
+        },
+    )
+    .await?;
+
+    outbox::enqueue(&db_txn, TRANSACTION_EVENTS_TOPIC, &response.transaction.txn_id, txn_data).await?;
+
+    db_txn.commit().await?;
+
+    Ok(SuccessResponse((Status::Accepted, Json(response))))
+}
+
+fn quote_unusable() -> ErrorResponse {
+    ErrorResponse((
+        Status::Conflict,
+        "FX quote has expired or was already used.".to_string(),
+    ))
 }
 
+async fn usable_quote(
+    db: &DatabaseConnection,
+    user: &AuthenticatedUser,
+    quote_id: &str,
+) -> Result<fx_quotes::Model, ErrorResponse> {
+    let quote = FxQuotes::find_by_id(quote_id.to_string())
+        .filter(fx_quotes::Column::UserId.eq(user.id.clone()))
+        .one(db)
+        .await?
+        .ok_or_else(|| ErrorResponse((Status::NotFound, "FX quote not found.".to_string())))?;
+
+    if quote.used_at.is_some() || quote.expires_at <= Utc::now() {
+        return Err(quote_unusable());
+    }
+
+    Ok(quote)
+}
+
+/// Finds the account a transfer pays into. A recipient named by email
+/// receives into their account in the sender's currency.
+async fn resolve_recipient(
+    db: &DatabaseConnection,
+    txn_req: &TransactionRequest,
+    sender: &account::Model,
+) -> Result<account::Model, ErrorResponse> {
+    let recipient = match (&txn_req.to_account_id, &txn_req.to_email) {
+        (Some(to_account_id), None) => Account::find_by_id(to_account_id.clone()).one(db).await?,
+        (None, Some(to_email)) => {
+            Account::find()
+                .inner_join(Users)
+                .filter(users::Column::Email.eq(to_email.clone()))
+                .filter(account::Column::CurrencyCode.eq(sender.currency_code.clone()))
+                .one(db)
+                .await?
+        }
+        _ => {
+            return Err(ErrorResponse((
+                Status::UnprocessableEntity,
+                "A transfer needs exactly one of to_account_id or to_email.".to_string(),
+            )));
+        }
+    };
+
+    let recipient = recipient.ok_or_else(|| {
+        ErrorResponse((Status::NotFound, "Recipient account not found.".to_string()))
+    })?;
+
+    if recipient.account_id == sender.account_id {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            "Cannot transfer to the same account.".to_string(),
+        )));
+    }
+
+    if recipient.currency_code != sender.currency_code {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            format!(
+                "Recipient account holds {}, transfers must be in {}.",
+                recipient.currency_code, sender.currency_code
+            ),
+        )));
+    }
+
+    Ok(recipient)
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(crate = "rocket::serde")]
+#[garde(context(TxnTypeContext))]
+pub struct RefundRequest {
+    /// Amount to refund; omit it to refund whatever is left of the purchase.
+    #[garde(custom(is_valid_refund_amount))]
+    #[serde(default, with = "rust_decimal::serde::str_option")]
+    amount: Option<Decimal>,
+}
+
+fn refund_target_not_found(tx_id: &str) -> ErrorResponse {
+    ErrorResponse((
+        Status::NotFound,
+        format!("Transaction with ID {} not found.", tx_id),
+    ))
+}
+
+#[post("/<tx_id>/refund", data = "<refund_req>")]
+pub async fn refund_transaction(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    idempotency_key: IdempotencyKey,
+    tx_id: &str,
+    refund_req: Json<RefundRequest>,
+) -> Response<Json<TransactionResponse>> {
+    let db = db.inner();
+
+    if let Err(report) = refund_req.validate() {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            format_validation_errors_json(report).to_string(),
+        )));
+    }
+
+    let request_hash = idempotency::fingerprint(&json!({
+        "refund_of": tx_id,
+        "amount": refund_req.amount.map(|amount| amount.normalize().to_string()),
+    }));
+
+    if let Some(key) = &idempotency_key.0
+        && let Some((status, replayed)) = idempotency::replay(db, &user.id, key, &request_hash).await?
+    {
+        return Ok(SuccessResponse((status, Json(replayed))));
+    }
+
+    let db_txn = db.begin().await?;
+
+    // Lock the purchase so concurrent refund requests see each other's
+    // pending refunds and cannot together exceed the original amount. The
+    // owner is filtered in the same query, so another user's row is never
+    // locked, and only the txn row is locked so settlement is not blocked.
+    let mut owned_purchase = Txns::find_by_id(tx_id.to_string())
+        .inner_join(Account)
+        .filter(account::Column::UserId.eq(user.id.clone()));
+    QueryTrait::query(&mut owned_purchase).lock_with_tables(LockType::Update, [txns::Entity]);
+    let original = owned_purchase
+        .one(&db_txn)
+        .await?
+        .ok_or_else(|| refund_target_not_found(tx_id))?;
+
+    if original.txn_type != "purchase" || original.status != TxnStatus::Succeeded {
+        return Err(ErrorResponse((
+            Status::Conflict,
+            "Only succeeded purchases can be refunded.".to_string(),
+        )));
+    }
+
+    let refundable = original.amount
+        - refunds::refunded_total(&db_txn, &original.txn_id, &refunds::OUTSTANDING).await?;
+    let amount = refund_req.amount.unwrap_or(refundable);
+
+    if refundable.is_zero() {
+        return Err(ErrorResponse((
+            Status::Conflict,
+            "Transaction has already been fully refunded.".to_string(),
+        )));
+    }
+
+    if amount > refundable {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            format!("amount exceeds the refundable balance of {}.", refundable),
+        )));
+    }
+
+    if !fits_currency_scale(&amount, &original.currency_code) {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            format!(
+                "amount has more decimal places than {} allows.",
+                original.currency_code
+            ),
+        )));
+    }
+
+    let refund_txn = txns::ActiveModel {
+        account_id: Set(original.account_id.clone()),
+        amount: Set(amount),
+        currency_code: Set(original.currency_code.clone()),
+        txn_type: Set(refunds::REFUND.to_string()),
+        status: Set(TxnStatus::Pending),
+        created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap())),
+        parent_txn_id: Set(Some(original.txn_id.clone())),
+        ..Default::default()
+    }
+    .insert(&db_txn)
+    .await?;
+
+    let txn_data = event_payload(&refund_txn);
+
+    let response = TransactionResponse {
+        status: "pending".to_string(),
+        message: "Refund created and queued for processing.".to_string(),
+        transaction: refund_txn.into(),
+    };
+
+    if let Some(key) = &idempotency_key.0
+        && let Err(err) =
+            idempotency::store(&db_txn, &user.id, key, &request_hash, Status::Accepted, &response).await
+    {
+        db_txn.rollback().await?;
+        if let Some(SqlErr::UniqueConstraintViolation(_)) = err.sql_err()
+            && let Some((status, replayed)) =
+                idempotency::replay(db, &user.id, key, &request_hash).await?
+        {
+            return Ok(SuccessResponse((status, Json(replayed))));
+        }
+        return Err(err.into());
+    }
+
+    txn_status::record(
+        &db_txn,
+        Transition {
+            txn_id: &response.transaction.txn_id,
+            from: None,
+            to: response.transaction.status,
+            reason_code: None,
+            message: None,
+            actor: &txn_status::user_actor(&user.id),
+        },
+    )
+    .await?;
+
+    outbox::enqueue(&db_txn, TRANSACTION_EVENTS_TOPIC, &response.transaction.txn_id, txn_data).await?;
+
+    db_txn.commit().await?;
+
+    Ok(SuccessResponse((Status::Accepted, Json(response))))
+}
 
 #[derive(Debug, Deserialize, Validate)]
 #[serde(crate = "rocket::serde")]
@@ -149,50 +533,125 @@ pub struct TransactionViewRequest {
     tx_id: String,
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct StatusChange {
+    from_status: Option<TxnStatus>,
+    to_status: TxnStatus,
+    reason_code: Option<String>,
+    message: Option<String>,
+    actor: String,
+    changed_at: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TransactionStatusResponse {
+    status: String,
+    message: String,
+    transaction: TransactionData,
+    failure_reason: Option<String>,
+    failure_message: Option<String>,
+    timeline: Vec<StatusChange>,
+}
+
+fn status_lookup_failed() -> ErrorResponse {
+    ErrorResponse((
+        Status::InternalServerError,
+        serde_json::to_string(&TransactionErrResponse {
+            status: "error".to_string(),
+            reason: "Failed to retrieve transaction details.".to_string(),
+        })
+        .unwrap(),
+    ))
+}
+
 #[get("/status/<tx_id>")]
 pub async fn get_transaction_status(
     db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
     tx_id: &str,
-) -> Response<Json<TransactionResponse>> {
+) -> Response<Json<TransactionStatusResponse>> {
+    let view_req = TransactionViewRequest {
+        tx_id: tx_id.to_string(),
+    };
+    if let Err(report) = view_req.validate() {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            format_validation_errors_json(report).to_string(),
+        )));
+    }
 
-    match Txns::find()
+    // Another user's transaction is reported as missing so that valid ids
+    // cannot be discovered by probing.
+    let txn = match Txns::find()
         .filter(txns::Column::TxnId.eq(tx_id))
+        .inner_join(Account)
+        .filter(account::Column::UserId.eq(user.id.clone()))
         .one(db.inner())
         .await
     {
-        Ok(Some(txn)) => Ok(SuccessResponse((
-            Status::Ok,
-            Json(TransactionResponse {
-                status: "success".to_string(),
-                message: "Transaction status retrieved.".to_string(),
-                transaction: TransactionData {
-                    txn_id: txn.txn_id,
-                    account_id: txn.account_id,
-                    amount: txn.amount,
-                    currency_code: txn.currency_code,
-                    txn_type: txn.txn_type,
-                    status: txn.status,
-                    created_at: txn.created_at.to_rfc3339(),
-                },
-            }),
-        ))),
-        Ok(None) => Err(ErrorResponse((
-            Status::NotFound,
-            serde_json::to_string(&TransactionErrResponse {
-                status: "error".to_string(),
-                reason: format!("Transaction with ID {} not found.", tx_id),
-            })
-            .unwrap(),
-        ))),
-        Err(_) => Err(ErrorResponse((
-            Status::InternalServerError,
-            serde_json::to_string(&TransactionErrResponse {
-                status: "error".to_string(),
-                reason: "Failed to retrieve transaction details.".to_string(),
-            })
-            .unwrap(),
-        ))),
-    }
+        Ok(Some(txn)) => txn,
+        Ok(None) => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                serde_json::to_string(&TransactionErrResponse {
+                    status: "error".to_string(),
+                    reason: format!("Transaction with ID {} not found.", tx_id),
+                })
+                .unwrap(),
+            )));
+        }
+        Err(_) => return Err(status_lookup_failed()),
+    };
+
+    let timeline = txn_status::timeline(db.inner(), &txn.txn_id)
+        .await
+        .map_err(|_| status_lookup_failed())?;
+
+    let failure_message = timeline
+        .iter()
+        .rev()
+        .find(|change| change.to_status == txn.status)
+        .and_then(|change| change.message.clone());
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(TransactionStatusResponse {
+            status: "success".to_string(),
+            message: "Transaction status retrieved.".to_string(),
+            failure_reason: txn.failure_reason.clone(),
+            failure_message,
+            transaction: txn.into(),
+            timeline: timeline
+                .into_iter()
+                .map(|change| StatusChange {
+                    from_status: change.from_status,
+                    to_status: change.to_status,
+                    reason_code: change.reason_code,
+                    message: change.message,
+                    actor: change.actor,
+                    changed_at: change.created_at.to_rfc3339(),
+                })
+                .collect(),
+        }),
+    )))
+}
+
+const DEFAULT_PAGE_SIZE: u64 = 20;
+const MAX_PAGE_SIZE: u64 = 100;
+
+#[derive(Debug, FromForm)]
+pub struct TransactionListQuery<'r> {
+    limit: Option<u64>,
+    cursor: Option<&'r str>,
+    account_id: Option<&'r str>,
+    status: Option<&'r str>,
+    txn_type: Option<&'r str>,
+    min_amount: Option<&'r str>,
+    max_amount: Option<&'r str>,
+    from: Option<&'r str>,
+    to: Option<&'r str>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -201,75 +660,122 @@ pub struct TransactionListResponse {
     status: String,
     message: String,
     transactions: Vec<TransactionData>,
+    next_cursor: Option<String>,
+}
+
+fn invalid_query(message: String) -> ErrorResponse {
+    ErrorResponse((Status::UnprocessableEntity, message))
+}
+
+/// Opaque position after `txn` in `(created_at, txn_id)` order.
+fn encode_cursor(txn: &txns::Model) -> String {
+    URL_SAFE_NO_PAD.encode(format!("{}|{}", txn.created_at.to_rfc3339(), txn.txn_id))
+}
+
+fn decode_cursor(cursor: &str) -> Option<(DateTime<FixedOffset>, String)> {
+    let decoded = String::from_utf8(URL_SAFE_NO_PAD.decode(cursor).ok()?).ok()?;
+    let (created_at, txn_id) = decoded.split_once('|')?;
+    Some((DateTime::parse_from_rfc3339(created_at).ok()?, txn_id.to_string()))
+}
+
+fn parse_amount(name: &str, value: Option<&str>) -> Result<Option<Decimal>, ErrorResponse> {
+    value
+        .map(|v| {
+            Decimal::from_str(v).map_err(|_| invalid_query(format!("{} must be a decimal amount.", name)))
+        })
+        .transpose()
 }
 
-#[get("/list")]
+pub fn parse_timestamp(name: &str, value: Option<&str>) -> Result<Option<DateTime<FixedOffset>>, ErrorResponse> {
+    value
+        .map(|v| {
+            DateTime::parse_from_rfc3339(v)
+                .map_err(|_| invalid_query(format!("{} must be an RFC 3339 timestamp.", name)))
+        })
+        .transpose()
+}
+
+/// Lists the caller's transactions newest first, one page at a time. Pass
+/// the returned `next_cursor` back as `cursor` to get the following page.
+#[get("/list?<query..>")]
 pub async fn list_transactions(
     db: &State<DatabaseConnection>,
     user: AuthenticatedUser,
+    query: TransactionListQuery<'_>,
 ) -> Response<Json<TransactionListResponse>> {
-    let accounts = match Account::find()
-        .filter(account::Column::UserId.eq(user.id.clone()))
-        .all(db.inner())
-        .await
-    {
-        Ok(accs) if !accs.is_empty() => accs,
-        Ok(_) => {
-            return Err(ErrorResponse((
-                Status::NotFound,
-                "No accounts found for this user.".to_string(),
-            )));
-        }
-        Err(_) => {
-            return Err(ErrorResponse((
-                Status::InternalServerError,
-                "Failed to retrieve accounts.".to_string(),
-            )));
-        }
-    };
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
 
-    let account_ids: Vec<String> = accounts.into_iter().map(|acc| acc.account_id).collect();
+    let mut select = Txns::find().filter(
+        txns::Column::AccountId.in_subquery(
+            user_accounts(&user.id, query.account_id)
+                .select_only()
+                .column(account::Column::AccountId)
+                .into_query(),
+        ),
+    );
 
-    let transactions = match Txns::find()
-        .filter(txns::Column::AccountId.is_in(account_ids))
+    if let Some(status) = query.status {
+        let status = TxnStatus::try_from_value(&status.to_string())
+            .map_err(|_| invalid_query(format!("Unknown status '{}'.", status)))?;
+        select = select.filter(txns::Column::Status.eq(status));
+    }
+    if let Some(txn_type) = query.txn_type {
+        select = select.filter(txns::Column::TxnType.eq(txn_type));
+    }
+    if let Some(min_amount) = parse_amount("min_amount", query.min_amount)? {
+        select = select.filter(txns::Column::Amount.gte(min_amount));
+    }
+    if let Some(max_amount) = parse_amount("max_amount", query.max_amount)? {
+        select = select.filter(txns::Column::Amount.lte(max_amount));
+    }
+    if let Some(from) = parse_timestamp("from", query.from)? {
+        select = select.filter(txns::Column::CreatedAt.gte(from));
+    }
+    if let Some(to) = parse_timestamp("to", query.to)? {
+        select = select.filter(txns::Column::CreatedAt.lt(to));
+    }
+    if let Some(cursor) = query.cursor {
+        let (created_at, txn_id) =
+            decode_cursor(cursor).ok_or_else(|| invalid_query("Invalid cursor.".to_string()))?;
+        select = select.filter(
+            Condition::any()
+                .add(txns::Column::CreatedAt.lt(created_at))
+                .add(
+                    Condition::all()
+                        .add(txns::Column::CreatedAt.eq(created_at))
+                        .add(txns::Column::TxnId.lt(txn_id)),
+                ),
+        );
+    }
+
+    // Fetch one extra row to learn whether another page follows.
+    let mut transactions = select
+        .order_by_desc(txns::Column::CreatedAt)
+        .order_by_desc(txns::Column::TxnId)
+        .limit(limit + 1)
         .all(db.inner())
         .await
-    {
-        Ok(txns) => txns,
-        Err(_) => {
-            return Err(ErrorResponse((
+        .map_err(|_| {
+            ErrorResponse((
                 Status::InternalServerError,
                 "Failed to retrieve transactions.".to_string(),
-            )));
-        }
-    };
+            ))
+        })?;
 
-    if transactions.is_empty() {
-        return Err(ErrorResponse((
-            Status::NotFound,
-            format!("No transactions found for user {}.", user.id),
-        )));
-    }
-
-    let transaction_list: Vec<TransactionData> = transactions
-        .into_iter()
-        .map(|txn| TransactionData {
-            txn_id: txn.txn_id,
-            account_id: txn.account_id,
-            amount: txn.amount,
-            currency_code: txn.currency_code,
-            txn_type: txn.txn_type,
-            status: txn.status,
-            created_at: txn.created_at.to_rfc3339(),
-        })
-        .collect();
+    let next_cursor = if transactions.len() as u64 > limit {
+        transactions.truncate(limit as usize);
+        transactions.last().map(encode_cursor)
+    } else {
+        None
+    };
 
     Ok(SuccessResponse((
         Status::Ok,
         Json(TransactionListResponse {
             status: "success".to_string(),
             message: "Transactions retrieved successfully.".to_string(),
-            transactions: transaction_list,
+            transactions: transactions.into_iter().map(TransactionData::from).collect(),
+            next_cursor,
         }),
     )))
 }
@@ -9,11 +9,12 @@ pub struct Model {
     pub account_id: String,
     pub user_id: String,
     pub currency_code: String,
-    #[sea_orm(column_type = "Float")]
-    pub balance: f32,
-    #[sea_orm(column_type = "Float")]
-    pub locked_balance: f32,
+    #[sea_orm(column_type = "Decimal(Some((20, 4)))")]
+    pub balance: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((20, 4)))")]
+    pub locked_balance: Decimal,
     pub updated_at: DateTimeWithTimeZone,
+    pub status: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -3,5 +3,16 @@
 pub mod prelude;
 
 pub mod account;
+pub mod admin_audit_log;
+pub mod fx_quotes;
+pub mod holds;
+pub mod idempotency_keys;
+pub mod ledger_entries;
+pub mod login_lockouts;
+pub mod mfa_recovery_codes;
+pub mod outbox_events;
+pub mod refresh_tokens;
+pub mod sea_orm_active_enums;
+pub mod txn_status_history;
 pub mod txns;
 pub mod users;
@@ -1,5 +1,15 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.11
 
 pub use super::account::Entity as Account;
+pub use super::admin_audit_log::Entity as AdminAuditLog;
+pub use super::fx_quotes::Entity as FxQuotes;
+pub use super::holds::Entity as Holds;
+pub use super::idempotency_keys::Entity as IdempotencyKeys;
+pub use super::ledger_entries::Entity as LedgerEntries;
+pub use super::login_lockouts::Entity as LoginLockouts;
+pub use super::mfa_recovery_codes::Entity as MfaRecoveryCodes;
+pub use super::outbox_events::Entity as OutboxEvents;
+pub use super::refresh_tokens::Entity as RefreshTokens;
+pub use super::txn_status_history::Entity as TxnStatusHistory;
 pub use super::txns::Entity as Txns;
 pub use super::users::Entity as Users;
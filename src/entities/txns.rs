@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.11
 
+use super::sea_orm_active_enums::TxnStatus;
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
@@ -8,12 +9,17 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub txn_id: String,
     pub account_id: String,
-    #[sea_orm(column_type = "Float")]
-    pub amount: f32,
+    #[sea_orm(column_type = "Decimal(Some((20, 4)))")]
+    pub amount: Decimal,
     pub currency_code: String,
     pub txn_type: String,
-    pub status: String,
+    pub status: TxnStatus,
     pub created_at: DateTimeWithTimeZone,
+    pub failure_reason: Option<String>,
+    pub parent_txn_id: Option<String>,
+    pub counterparty_account_id: Option<String>,
+    #[sea_orm(column_type = "Decimal(Some((20, 10)))", nullable)]
+    pub fx_rate: Option<Decimal>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -26,6 +32,26 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     Account,
+    #[sea_orm(
+        belongs_to = "super::account::Entity",
+        from = "Column::CounterpartyAccountId",
+        to = "super::account::Column::AccountId",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    CounterpartyAccount,
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::ParentTxnId",
+        to = "Column::TxnId",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    SelfRef,
+    #[sea_orm(has_many = "super::ledger_entries::Entity")]
+    LedgerEntries,
+    #[sea_orm(has_many = "super::txn_status_history::Entity")]
+    TxnStatusHistory,
 }
 
 impl Related<super::account::Entity> for Entity {
@@ -34,4 +60,16 @@ impl Related<super::account::Entity> for Entity {
     }
 }
 
+impl Related<super::ledger_entries::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LedgerEntries.def()
+    }
+}
+
+impl Related<super::txn_status_history::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TxnStatusHistory.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
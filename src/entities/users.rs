@@ -15,12 +15,19 @@ pub struct Model {
     pub profile_data: Json,
     pub created_at: DateTimeWithTimeZone,
     pub kyc_status: String,
+    pub role: String,
+    pub mfa_secret: Option<String>,
+    pub mfa_enabled: bool,
+    pub mfa_last_step: Option<i64>,
+    pub email_verified: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::account::Entity")]
     Account,
+    #[sea_orm(has_many = "super::idempotency_keys::Entity")]
+    IdempotencyKeys,
 }
 
 impl Related<super::account::Entity> for Entity {
@@ -29,4 +36,10 @@ impl Related<super::account::Entity> for Entity {
     }
 }
 
+impl Related<super::idempotency_keys::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::IdempotencyKeys.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
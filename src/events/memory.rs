@@ -0,0 +1,51 @@
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+use super::{Event, EventError, EventPublisher, EventSubscriber};
+
+/// In-process event bus backed by a tokio channel. Events are delivered to
+/// the single subscriber in publish order and are lost on restart, so this is
+/// only meant for tests and local development.
+pub fn channel(topics: &[&str]) -> (InMemoryPublisher, InMemorySubscriber) {
+    let (sender, receiver) = unbounded_channel();
+    (
+        InMemoryPublisher { sender },
+        InMemorySubscriber {
+            receiver,
+            topics: topics.iter().map(|t| t.to_string()).collect(),
+        },
+    )
+}
+
+pub struct InMemoryPublisher {
+    sender: UnboundedSender<Event>,
+}
+
+#[rocket::async_trait]
+impl EventPublisher for InMemoryPublisher {
+    async fn publish(&self, topic: &str, key: &str, payload: &str) -> Result<(), EventError> {
+        self.sender
+            .send(Event {
+                topic: topic.to_string(),
+                key: key.to_string(),
+                payload: payload.to_string(),
+            })
+            .map_err(|_| EventError("In-memory subscriber has been dropped".to_string()))
+    }
+}
+
+pub struct InMemorySubscriber {
+    receiver: UnboundedReceiver<Event>,
+    topics: Vec<String>,
+}
+
+#[rocket::async_trait]
+impl EventSubscriber for InMemorySubscriber {
+    async fn recv(&mut self) -> Result<Option<Event>, EventError> {
+        while let Some(event) = self.receiver.recv().await {
+            if self.topics.contains(&event.topic) {
+                return Ok(Some(event));
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+use std::sync::Arc;
+
+use rdkafka::error::KafkaError;
+use sea_orm::DatabaseConnection;
+
+use crate::AppConfig;
+use crate::kafka::{TRANSACTION_EVENTS_TOPIC, consumer::KafkaSubscriber, producer::KafkaProducer};
+use crate::processing::{self, TransactionEvent, TransactionProcessor};
+
+pub mod memory;
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub topic: String,
+    pub key: String,
+    pub payload: String,
+}
+
+#[derive(Debug)]
+pub struct EventError(pub String);
+
+impl fmt::Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<KafkaError> for EventError {
+    fn from(err: KafkaError) -> Self {
+        EventError(err.to_string())
+    }
+}
+
+#[rocket::async_trait]
+pub trait EventPublisher: Send + Sync {
+    async fn publish(&self, topic: &str, key: &str, payload: &str) -> Result<(), EventError>;
+
+    /// Waits for buffered events to be delivered. Called once on shutdown.
+    fn flush(&self) -> Result<(), EventError> {
+        Ok(())
+    }
+}
+
+#[rocket::async_trait]
+pub trait EventSubscriber: Send {
+    /// Waits for the next event. `Ok(None)` means the bus was closed and no
+    /// more events will arrive.
+    async fn recv(&mut self) -> Result<Option<Event>, EventError>;
+}
+
+/// Builds the publisher/subscriber pair selected by `PAYMENTS_EVENT_BUS`
+/// (`kafka`, the default, or `memory` for tests and local runs without a
+/// broker).
+pub fn build(config: &AppConfig) -> (Arc<dyn EventPublisher>, Box<dyn EventSubscriber>) {
+    match config.event_bus.as_str() {
+        "memory" => {
+            let (publisher, subscriber) = memory::channel(&[TRANSACTION_EVENTS_TOPIC]);
+            (Arc::new(publisher), Box::new(subscriber))
+        }
+        "kafka" => (
+            Arc::new(KafkaProducer::new(config)),
+            Box::new(KafkaSubscriber::new(&config.kafka_brokers, &[TRANSACTION_EVENTS_TOPIC])),
+        ),
+        other => panic!("Unknown PAYMENTS_EVENT_BUS '{}', expected 'kafka' or 'memory'.", other),
+    }
+}
+
+/// Consumes transaction events and hands each one to the processor.
+pub async fn consume(
+    mut subscriber: Box<dyn EventSubscriber>,
+    db: DatabaseConnection,
+    processor: Arc<dyn TransactionProcessor>,
+) {
+    loop {
+        match subscriber.recv().await {
+            Err(e) => println!("Event bus error: {}", e),
+            Ok(None) => {
+                println!("Event bus closed, stopping consumer");
+                return;
+            }
+            Ok(Some(event)) => {
+                println!("Message consumed: {}", event.payload);
+                if let Ok(payload) = serde_json::from_str::<TransactionEvent>(&event.payload) {
+                    tokio::spawn(processing::handle_transaction(
+                        payload,
+                        db.clone(),
+                        processor.clone(),
+                    ));
+                } else {
+                    println!("Failed to deserialize message");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use rocket::{
+    Orbit, Rocket,
+    fairing::{Fairing, Info, Kind},
+};
+
+use crate::events::EventPublisher;
+
+/// Flushes the shared event publisher when Rocket shuts down so in-flight
+/// messages are not dropped.
+pub struct FlushPublisher;
+
+#[rocket::async_trait]
+impl Fairing for FlushPublisher {
+    fn info(&self) -> Info {
+        Info {
+            name: "Flush event publisher on shutdown",
+            kind: Kind::Shutdown,
+        }
+    }
+
+    async fn on_shutdown(&self, rocket: &Rocket<Orbit>) {
+        let Some(publisher) = rocket.state::<Arc<dyn EventPublisher>>().cloned() else {
+            return;
+        };
+
+        match tokio::task::spawn_blocking(move || publisher.flush()).await {
+            Ok(Ok(())) => println!("✅ Event publisher flushed."),
+            Ok(Err(e)) => eprintln!("❌ Failed to flush event publisher: {}", e),
+            Err(e) => eprintln!("❌ Flush task panicked: {}", e),
+        }
+    }
+}
@@ -1 +1,2 @@
 pub mod cors;
+pub mod event_bus;
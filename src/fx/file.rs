@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+use rust_decimal::Decimal;
+
+use super::{FxError, FxRateProvider, RateTable};
+
+/// Rates read from a JSON file mapping currency codes to units per one unit
+/// of a reference currency, e.g. `{"USD": "1", "INR": "83.50"}`. The file is
+/// re-read whenever its modification time changes, so rates can be updated
+/// without a restart.
+pub struct FileRates {
+    path: PathBuf,
+    loaded: RwLock<(SystemTime, RateTable)>,
+}
+
+impl FileRates {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, FxError> {
+        let path = path.into();
+        let loaded = read(&path)?;
+        Ok(FileRates {
+            path,
+            loaded: RwLock::new(loaded),
+        })
+    }
+
+    fn refresh(&self) -> Result<(), FxError> {
+        let modified = modified_at(&self.path)?;
+        if modified == self.loaded.read().unwrap().0 {
+            return Ok(());
+        }
+        *self.loaded.write().unwrap() = read(&self.path)?;
+        Ok(())
+    }
+}
+
+fn modified_at(path: &PathBuf) -> Result<SystemTime, FxError> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .map_err(|e| FxError(e.to_string()))
+}
+
+fn read(path: &PathBuf) -> Result<(SystemTime, RateTable), FxError> {
+    let modified = modified_at(path)?;
+    let contents = std::fs::read_to_string(path).map_err(|e| FxError(e.to_string()))?;
+    let rates: HashMap<String, String> =
+        serde_json::from_str(&contents).map_err(|e| FxError(e.to_string()))?;
+
+    let rates = rates
+        .into_iter()
+        .map(|(code, units)| {
+            units
+                .parse::<Decimal>()
+                .map(|units| (code.clone(), units))
+                .map_err(|e| FxError(format!("invalid rate for {}: {}", code, e)))
+        })
+        .collect::<Result<HashMap<_, _>, _>>()?;
+
+    Ok((modified, RateTable::new(rates)))
+}
+
+#[rocket::async_trait]
+impl FxRateProvider for FileRates {
+    async fn rate(&self, from: &str, to: &str) -> Result<Option<Decimal>, FxError> {
+        // Keep serving the last good table if the file is mid-rewrite.
+        if let Err(e) = self.refresh() {
+            eprintln!("Failed to reload FX rates from {}: {}", self.path.display(), e);
+        }
+        Ok(self.loaded.read().unwrap().1.rate(from, to))
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::AppConfig;
+use crate::utils::money::minor_units;
+
+pub mod file;
+pub mod table;
+
+use file::FileRates;
+use table::StaticRates;
+
+/// Digits kept on a quoted rate; matches the `NUMERIC(20, 10)` rate columns.
+pub const RATE_SCALE: u32 = 10;
+
+#[derive(Debug)]
+pub struct FxError(pub String);
+
+impl fmt::Display for FxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Source of exchange rates. `rate(from, to)` is how many units of `to` one
+/// unit of `from` buys; `Ok(None)` means the pair is not priced.
+#[rocket::async_trait]
+pub trait FxRateProvider: Send + Sync {
+    async fn rate(&self, from: &str, to: &str) -> Result<Option<Decimal>, FxError>;
+}
+
+/// Rates expressed as units of each currency per one unit of a common
+/// reference currency, from which every cross rate is derived.
+#[derive(Debug, Clone, Default)]
+pub struct RateTable(HashMap<String, Decimal>);
+
+impl RateTable {
+    pub fn new(units_per_reference: HashMap<String, Decimal>) -> Self {
+        RateTable(units_per_reference)
+    }
+
+    pub fn rate(&self, from: &str, to: &str) -> Option<Decimal> {
+        if from == to {
+            return self.0.contains_key(from).then_some(Decimal::ONE);
+        }
+        let from_units = self.0.get(from).filter(|units| !units.is_zero())?;
+        let to_units = self.0.get(to)?;
+        Some((to_units / from_units).round_dp(RATE_SCALE))
+    }
+}
+
+/// Converts `amount` at `rate` into `to_currency`, truncated to that
+/// currency's minor unit so the platform never pays out a fraction it did
+/// not receive.
+pub fn convert(amount: Decimal, rate: Decimal, to_currency: &str) -> Decimal {
+    let scale = minor_units(to_currency).unwrap_or(2);
+    (amount * rate).round_dp_with_strategy(scale, RoundingStrategy::ToZero)
+}
+
+/// Builds the provider selected by `PAYMENTS_FX_PROVIDER` (`static`, the
+/// default, or `file` reading `PAYMENTS_FX_RATES_FILE`).
+pub fn build(config: &AppConfig) -> Arc<dyn FxRateProvider> {
+    match config.fx_provider.as_str() {
+        "static" => Arc::new(StaticRates::default()),
+        "file" => match FileRates::load(&config.fx_rates_file) {
+            Ok(rates) => Arc::new(rates),
+            Err(e) => panic!("Failed to load FX rates from {}: {}", config.fx_rates_file, e),
+        },
+        other => panic!("Unknown PAYMENTS_FX_PROVIDER '{}', expected 'static' or 'file'.", other),
+    }
+}
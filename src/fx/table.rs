@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+
+use super::{FxError, FxRateProvider, RateTable};
+
+/// Indicative mid-market rates, in units per US dollar, for every currency
+/// accounts can be opened in.
+const UNITS_PER_USD: [(&str, i64, u32); 29] = [
+    ("USD", 1, 0),
+    ("EUR", 92, 2),
+    ("GBP", 79, 2),
+    ("INR", 8350, 2),
+    ("JPY", 157, 0),
+    ("AUD", 150, 2),
+    ("CAD", 137, 2),
+    ("CHF", 90, 2),
+    ("CNY", 725, 2),
+    ("SGD", 135, 2),
+    ("AED", 36725, 4),
+    ("HKD", 782, 2),
+    ("NZD", 164, 2),
+    ("SEK", 1050, 2),
+    ("NOK", 1060, 2),
+    ("DKK", 688, 2),
+    ("ZAR", 1850, 2),
+    ("BRL", 540, 2),
+    ("MXN", 1820, 2),
+    ("KRW", 1370, 0),
+    ("VND", 25400, 0),
+    ("CLP", 930, 0),
+    ("ISK", 138, 0),
+    ("UGX", 3700, 0),
+    ("BHD", 376, 3),
+    ("KWD", 307, 3),
+    ("OMR", 385, 3),
+    ("JOD", 709, 3),
+    ("TND", 310, 2),
+];
+
+/// Fixed, built-in rate table for offline and local use.
+pub struct StaticRates(RateTable);
+
+impl StaticRates {
+    pub fn new(table: RateTable) -> Self {
+        StaticRates(table)
+    }
+}
+
+impl Default for StaticRates {
+    fn default() -> Self {
+        StaticRates(RateTable::new(
+            UNITS_PER_USD
+                .iter()
+                .map(|&(code, units, scale)| (code.to_string(), Decimal::new(units, scale)))
+                .collect::<HashMap<_, _>>(),
+        ))
+    }
+}
+
+#[rocket::async_trait]
+impl FxRateProvider for StaticRates {
+    async fn rate(&self, from: &str, to: &str) -> Result<Option<Decimal>, FxError> {
+        Ok(self.0.rate(from, to))
+    }
+}
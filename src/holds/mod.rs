@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use chrono::{FixedOffset, Utc};
+use rust_decimal::Decimal;
+use sea_orm::sea_query::{Expr, LockBehavior, LockType};
+use sea_orm::*;
+
+use crate::entities::{account, holds, prelude::*};
+
+pub const ACTIVE: &str = "active";
+pub const CAPTURED: &str = "captured";
+pub const VOIDED: &str = "voided";
+pub const EXPIRED: &str = "expired";
+
+const BATCH_SIZE: u64 = 100;
+
+/// Ends an active hold. Its whole amount leaves `locked_balance`, and the
+/// `captured` part also leaves `balance`. Run it inside the transaction
+/// that locked the hold row.
+pub async fn close<C: ConnectionTrait>(
+    db: &C,
+    hold: holds::Model,
+    status: &str,
+    captured: Decimal,
+    capture_txn_id: Option<String>,
+) -> Result<holds::Model, DbErr> {
+    let now = Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap());
+
+    Account::update_many()
+        .col_expr(account::Column::Balance, Expr::col(account::Column::Balance).sub(captured))
+        .col_expr(
+            account::Column::LockedBalance,
+            Expr::col(account::Column::LockedBalance).sub(hold.amount),
+        )
+        .col_expr(account::Column::UpdatedAt, Expr::value(now))
+        .filter(account::Column::AccountId.eq(hold.account_id.clone()))
+        .exec(db)
+        .await?;
+
+    let mut hold_am = hold.into_active_model();
+    hold_am.status = Set(status.to_string());
+    hold_am.capture_txn_id = Set(capture_txn_id);
+    hold_am.updated_at = Set(now);
+    hold_am.update(db).await
+}
+
+/// Sweeper loop: releases holds that were neither captured nor voided
+/// before they expired.
+pub async fn start_sweeper(db: DatabaseConnection, interval: Duration) {
+    loop {
+        match expire_due(&db).await {
+            Ok(0) => tokio::time::sleep(interval).await,
+            Ok(expired) => println!("Released {} expired holds", expired),
+            Err(e) => {
+                eprintln!("Hold sweeper error: {}", e);
+                tokio::time::sleep(interval).await;
+            }
+        }
+    }
+}
+
+async fn expire_due(db: &DatabaseConnection) -> Result<usize, DbErr> {
+    let db_txn = db.begin().await?;
+    let now = Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap());
+
+    // SKIP LOCKED leaves holds that are being captured or voided right now
+    // to that request; the next sweep picks them up if they are still active.
+    let due = Holds::find()
+        .filter(holds::Column::Status.eq(ACTIVE))
+        .filter(holds::Column::ExpiresAt.lte(now))
+        .order_by_asc(holds::Column::ExpiresAt)
+        .limit(BATCH_SIZE)
+        .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+        .all(&db_txn)
+        .await?;
+
+    let expired = due.len();
+    for hold in due {
+        close(&db_txn, hold, EXPIRED, Decimal::ZERO, None).await?;
+    }
+
+    db_txn.commit().await?;
+
+    Ok(expired)
+}
@@ -0,0 +1,100 @@
+use chrono::{FixedOffset, Utc};
+use rocket::{
+    http::Status,
+    request::{self, FromRequest, Outcome, Request},
+    serde::{Serialize, de::DeserializeOwned},
+};
+use sea_orm::*;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    controllers::ErrorResponse,
+    entities::{idempotency_keys, prelude::IdempotencyKeys},
+};
+
+const MAX_KEY_LEN: usize = 255;
+
+/// Optional `Idempotency-Key` request header.
+pub struct IdempotencyKey(pub Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IdempotencyKey {
+    type Error = String;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        match req.headers().get_one("Idempotency-Key") {
+            None => Outcome::Success(IdempotencyKey(None)),
+            Some(key) if key.is_empty() || key.len() > MAX_KEY_LEN => Outcome::Error((
+                Status::UnprocessableEntity,
+                "Idempotency-Key must be 1-255 characters".to_string(),
+            )),
+            Some(key) => Outcome::Success(IdempotencyKey(Some(key.to_string()))),
+        }
+    }
+}
+
+/// Stable hash of the parts of a request that decide its outcome.
+pub fn fingerprint(request: &serde_json::Value) -> String {
+    format!("{:x}", Sha256::digest(request.to_string().as_bytes()))
+}
+
+/// Returns the stored response for `(user_id, key)` if this request was
+/// already processed, or a 422 if the key was used for a different request.
+pub async fn replay<T: DeserializeOwned, C: ConnectionTrait>(
+    db: &C,
+    user_id: &str,
+    key: &str,
+    request_hash: &str,
+) -> Result<Option<(Status, T)>, ErrorResponse> {
+    let record = match IdempotencyKeys::find_by_id((user_id.to_string(), key.to_string()))
+        .one(db)
+        .await?
+    {
+        Some(record) => record,
+        None => return Ok(None),
+    };
+
+    if record.request_hash != request_hash {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            "Idempotency-Key was already used with a different request.".to_string(),
+        )));
+    }
+
+    let body = serde_json::from_value(record.response_body).map_err(|_| {
+        ErrorResponse((
+            Status::InternalServerError,
+            "Failed to read stored idempotent response.".to_string(),
+        ))
+    })?;
+
+    Ok(Some((Status::new(record.response_status as u16), body)))
+}
+
+/// Records the response for `(user_id, key)`. Run this in the same database
+/// transaction as the side effects it guards; a concurrent request with the
+/// same key fails here with a unique violation and should fall back to
+/// [`replay`].
+pub async fn store<T: Serialize, C: ConnectionTrait>(
+    db: &C,
+    user_id: &str,
+    key: &str,
+    request_hash: &str,
+    status: Status,
+    response: &T,
+) -> Result<(), DbErr> {
+    let body = serde_json::to_value(response).map_err(|e| DbErr::Custom(e.to_string()))?;
+
+    IdempotencyKeys::insert(idempotency_keys::ActiveModel {
+        user_id: Set(user_id.to_string()),
+        idempotency_key: Set(key.to_string()),
+        request_hash: Set(request_hash.to_string()),
+        response_status: Set(status.code as i16),
+        response_body: Set(body),
+        created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap())),
+    })
+    .exec_without_returning(db)
+    .await?;
+
+    Ok(())
+}
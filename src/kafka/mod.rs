@@ -1,2 +1,4 @@
 pub mod consumer;
-pub mod producer;
\ No newline at end of file
+pub mod producer;
+
+pub const TRANSACTION_EVENTS_TOPIC: &str = "transaction-events";
@@ -1,22 +1,56 @@
 use std::time::Duration;
 use rdkafka::ClientConfig;
-use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
 use rdkafka::util::Timeout;
 
-pub fn create_producer() -> FutureProducer {
-    ClientConfig::new()
-        .set("bootstrap.servers", "localhost:9092")
-        .create()
-        .expect("Failed to create Kafka producer")
+use crate::AppConfig;
+use crate::events::{EventError, EventPublisher};
+
+/// Process-wide Kafka producer. Built once at startup and shared (it is
+/// cheap to clone) so every publish reuses the same librdkafka client and
+/// broker connections.
+#[derive(Clone)]
+pub struct KafkaProducer {
+    inner: FutureProducer,
+    delivery_timeout: Duration,
 }
 
-pub async fn produce(producer: FutureProducer, msg: String) {
-    let record = FutureRecord::to("transaction-events")
-        .payload(&msg)
-        .key("txn");
+impl KafkaProducer {
+    pub fn new(config: &AppConfig) -> Self {
+        let inner = ClientConfig::new()
+            .set("bootstrap.servers", &config.kafka_brokers)
+            .set("acks", &config.kafka_acks)
+            .set("compression.type", &config.kafka_compression)
+            .set("message.timeout.ms", config.kafka_delivery_timeout_ms.to_string())
+            .create()
+            .expect("Failed to create Kafka producer");
+
+        Self {
+            inner,
+            delivery_timeout: Duration::from_millis(config.kafka_delivery_timeout_ms),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl EventPublisher for KafkaProducer {
+    async fn publish(&self, topic: &str, key: &str, payload: &str) -> Result<(), EventError> {
+        let record = FutureRecord::to(topic).payload(payload).key(key);
+
+        match self.inner.send(record, Timeout::After(self.delivery_timeout)).await {
+            Ok(delivery) => {
+                println!("Message sent: {:?}", delivery);
+                Ok(())
+            }
+            Err((err, _)) => Err(err.into()),
+        }
+    }
 
-    match producer.send(record, Timeout::After(Duration::from_secs(1))).await {
-        Ok(delivery) => println!("Message sent: {:?}", delivery),
-        Err((err, _)) => eprintln!("Failed to send message: {:?}", err),
+    /// Blocks until queued messages are delivered or the delivery timeout
+    /// elapses.
+    fn flush(&self) -> Result<(), EventError> {
+        self.inner
+            .flush(Timeout::After(self.delivery_timeout))
+            .map_err(EventError::from)
     }
 }
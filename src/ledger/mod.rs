@@ -0,0 +1,91 @@
+use chrono::{FixedOffset, Utc};
+use rust_decimal::Decimal;
+use sea_orm::*;
+
+use crate::entities::{ledger_entries, prelude::LedgerEntries};
+
+pub const DEBIT: &str = "debit";
+pub const CREDIT: &str = "credit";
+
+/// System account that absorbs the other side of money entering or leaving
+/// the platform in a given currency.
+pub fn clearing_account(currency_code: &str) -> String {
+    format!("clearing-{}", currency_code)
+}
+
+/// Writes one balanced pair of postings: `amount` is debited from
+/// `debit_account` and credited to `credit_account`. Callers run this on the
+/// same connection/transaction that mutates `account.balance`.
+pub async fn post<C: ConnectionTrait>(
+    db: &C,
+    txn_id: Option<String>,
+    debit_account: String,
+    credit_account: String,
+    amount: Decimal,
+    currency_code: &str,
+) -> Result<(), DbErr> {
+    let now = Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap());
+
+    LedgerEntries::insert_many([
+        ledger_entries::ActiveModel {
+            txn_id: Set(txn_id.clone()),
+            account_id: Set(debit_account),
+            entry_type: Set(DEBIT.to_string()),
+            amount: Set(amount),
+            currency_code: Set(currency_code.to_string()),
+            created_at: Set(now),
+            ..Default::default()
+        },
+        ledger_entries::ActiveModel {
+            txn_id: Set(txn_id),
+            account_id: Set(credit_account),
+            entry_type: Set(CREDIT.to_string()),
+            amount: Set(amount),
+            currency_code: Set(currency_code.to_string()),
+            created_at: Set(now),
+            ..Default::default()
+        },
+    ])
+    .exec(db)
+    .await?;
+
+    Ok(())
+}
+
+#[derive(Debug, FromQueryResult)]
+pub struct BalanceMismatch {
+    pub account_id: String,
+    pub recorded: Decimal,
+    pub computed: Decimal,
+}
+
+/// Recomputes every `account.balance` from its postings (credits minus
+/// debits) and returns the accounts where the two disagree.
+pub async fn check_invariants<C: ConnectionTrait>(db: &C) -> Result<Vec<BalanceMismatch>, DbErr> {
+    BalanceMismatch::find_by_statement(Statement::from_string(
+        db.get_database_backend(),
+        "SELECT a.account_id, a.balance AS recorded, \
+                COALESCE(SUM(CASE WHEN l.entry_type = 'credit' THEN l.amount ELSE -l.amount END), 0) AS computed \
+         FROM account a \
+         LEFT JOIN ledger_entries l ON l.account_id = a.account_id \
+         GROUP BY a.account_id, a.balance \
+         HAVING a.balance <> COALESCE(SUM(CASE WHEN l.entry_type = 'credit' THEN l.amount ELSE -l.amount END), 0)",
+    ))
+    .all(db)
+    .await
+}
+
+pub async fn report_mismatches(db: &DatabaseConnection) {
+    match check_invariants(db).await {
+        Ok(mismatches) if mismatches.is_empty() => println!("✅ Ledger matches account balances."),
+        Ok(mismatches) => {
+            for m in mismatches {
+                eprintln!(
+                    "❌ Ledger mismatch for account {}: recorded {}, postings sum to {}",
+                    m.account_id, m.recorded, m.computed
+                );
+            }
+        }
+        Err(e) => eprintln!("Failed to run ledger invariant check: {}", e),
+    }
+}
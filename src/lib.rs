@@ -1,27 +1,80 @@
 #[macro_use]
 extern crate rocket;
+use auth::throttle::{LoginThrottle, MemoryAttemptStore};
 use controllers::{Response, SuccessResponse};
 use fairings::cors::{CORS, options};
+use fairings::event_bus::FlushPublisher;
 use migrator::Migrator;
 use rocket::{Build, Rocket, http::Status};
+use rust_decimal::Decimal;
 use sea_orm_migration::MigratorTrait;
+use std::sync::Arc;
 
 mod auth;
 mod controllers;
 mod db;
-mod entities;
+pub mod entities;
+pub mod events;
 mod fairings;
+pub mod fx;
+mod holds;
+mod idempotency;
+pub mod ledger;
 mod migrator;
+mod outbox;
+pub mod processing;
+mod refunds;
+mod statements;
+pub mod txn_status;
 mod utils;
 mod kafka;
+mod mailer;
 
 pub struct AppConfig {
-    db_host: String,
-    db_port: String,
-    db_username: String,
-    db_password: String,
-    db_database: String,
-    jwt_secret: String,
+    pub db_host: String,
+    pub db_port: String,
+    pub db_username: String,
+    pub db_password: String,
+    pub db_database: String,
+    pub jwt_key_dir: String,
+    pub jwt_signing_kid: Option<String>,
+    pub jwt_issuer: String,
+    pub jwt_audience: String,
+    pub access_token_ttl_secs: u64,
+    pub refresh_token_ttl_secs: i64,
+    pub login_max_failures: u32,
+    pub login_ip_max_failures: u32,
+    pub login_backoff_base_secs: u64,
+    pub login_lockout_secs: u64,
+    pub password_min_length: usize,
+    pub password_min_classes: usize,
+    pub password_blocklist_file: Option<String>,
+    pub mfa_issuer: String,
+    pub mfa_pending_ttl_secs: u64,
+    pub mfa_step_up_amount: Decimal,
+    pub mfa_step_up_currency: String,
+    pub email_verification_ttl_secs: u64,
+    pub password_reset_ttl_secs: u64,
+    pub mailer: String,
+    pub mail_dir: String,
+    pub mail_from: String,
+    pub app_url: String,
+    pub kafka_brokers: String,
+    pub kafka_acks: String,
+    pub kafka_compression: String,
+    pub kafka_delivery_timeout_ms: u64,
+    pub event_bus: String,
+    pub processor: String,
+    pub max_txn_amount: Decimal,
+    pub sim_failure_rate: f64,
+    pub sim_latency_ms: u64,
+    pub sim_seed: u64,
+    pub hold_ttl_secs: i64,
+    pub hold_sweep_interval_secs: u64,
+    pub fx_provider: String,
+    pub fx_rates_file: String,
+    pub statement_page_size: u64,
+    pub fx_quote_ttl_secs: i64,
 }
 
 impl Default for AppConfig {
@@ -32,8 +85,110 @@ impl Default for AppConfig {
             db_username: std::env::var("PAYMENTS_DB_USERNAME").unwrap_or("postgres".to_string()),
             db_password: std::env::var("PAYMENTS_DB_PASSWORD").unwrap_or("root".to_string()),
             db_database: std::env::var("PAYMENTS_DB_DATABASE").unwrap_or("payments".to_string()),
-            jwt_secret: std::env::var("PAYMENTS_JWT_SECRET")
-                .expect("Please set the PAYMENTS_JWT_SECRET env variable."),
+            jwt_key_dir: std::env::var("PAYMENTS_JWT_KEY_DIR").unwrap_or("keys".to_string()),
+            jwt_signing_kid: std::env::var("PAYMENTS_JWT_SIGNING_KID").ok(),
+            jwt_issuer: std::env::var("PAYMENTS_JWT_ISSUER").unwrap_or("payments".to_string()),
+            jwt_audience: std::env::var("PAYMENTS_JWT_AUDIENCE").unwrap_or("payments-api".to_string()),
+            access_token_ttl_secs: std::env::var("PAYMENTS_ACCESS_TOKEN_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(15 * 60),
+            refresh_token_ttl_secs: std::env::var("PAYMENTS_REFRESH_TOKEN_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30 * 24 * 60 * 60),
+            login_max_failures: std::env::var("PAYMENTS_LOGIN_MAX_FAILURES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5),
+            login_ip_max_failures: std::env::var("PAYMENTS_LOGIN_IP_MAX_FAILURES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(20),
+            login_backoff_base_secs: std::env::var("PAYMENTS_LOGIN_BACKOFF_BASE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1),
+            login_lockout_secs: std::env::var("PAYMENTS_LOGIN_LOCKOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(15 * 60),
+            password_min_length: std::env::var("PAYMENTS_PASSWORD_MIN_LENGTH")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(12),
+            password_min_classes: std::env::var("PAYMENTS_PASSWORD_MIN_CLASSES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2),
+            password_blocklist_file: std::env::var("PAYMENTS_PASSWORD_BLOCKLIST_FILE").ok(),
+            mfa_issuer: std::env::var("PAYMENTS_MFA_ISSUER").unwrap_or("Payments".to_string()),
+            mfa_pending_ttl_secs: std::env::var("PAYMENTS_MFA_PENDING_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5 * 60),
+            mfa_step_up_amount: std::env::var("PAYMENTS_MFA_STEP_UP_AMOUNT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(Decimal::new(10_000, 0)),
+            mfa_step_up_currency: std::env::var("PAYMENTS_MFA_STEP_UP_CURRENCY")
+                .unwrap_or("INR".to_string()),
+            email_verification_ttl_secs: std::env::var("PAYMENTS_EMAIL_VERIFICATION_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(24 * 60 * 60),
+            password_reset_ttl_secs: std::env::var("PAYMENTS_PASSWORD_RESET_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60 * 60),
+            mailer: std::env::var("PAYMENTS_MAILER").unwrap_or("log".to_string()),
+            mail_dir: std::env::var("PAYMENTS_MAIL_DIR").unwrap_or("mail".to_string()),
+            mail_from: std::env::var("PAYMENTS_MAIL_FROM").unwrap_or("no-reply@payments.local".to_string()),
+            app_url: std::env::var("PAYMENTS_APP_URL").unwrap_or("http://localhost:8000".to_string()),
+            kafka_brokers: std::env::var("PAYMENTS_KAFKA_BROKERS").unwrap_or("localhost:9092".to_string()),
+            kafka_acks: std::env::var("PAYMENTS_KAFKA_ACKS").unwrap_or("all".to_string()),
+            kafka_compression: std::env::var("PAYMENTS_KAFKA_COMPRESSION").unwrap_or("none".to_string()),
+            kafka_delivery_timeout_ms: std::env::var("PAYMENTS_KAFKA_DELIVERY_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5000),
+            event_bus: std::env::var("PAYMENTS_EVENT_BUS").unwrap_or("kafka".to_string()),
+            processor: std::env::var("PAYMENTS_PROCESSOR").unwrap_or("rules".to_string()),
+            max_txn_amount: std::env::var("PAYMENTS_MAX_TXN_AMOUNT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(Decimal::new(100_000, 0)),
+            sim_failure_rate: std::env::var("PAYMENTS_SIM_FAILURE_RATE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0.1),
+            sim_latency_ms: std::env::var("PAYMENTS_SIM_LATENCY_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(200),
+            sim_seed: std::env::var("PAYMENTS_SIM_SEED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(42),
+            hold_ttl_secs: std::env::var("PAYMENTS_HOLD_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(7 * 24 * 60 * 60),
+            hold_sweep_interval_secs: std::env::var("PAYMENTS_HOLD_SWEEP_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60),
+            fx_provider: std::env::var("PAYMENTS_FX_PROVIDER").unwrap_or("static".to_string()),
+            fx_rates_file: std::env::var("PAYMENTS_FX_RATES_FILE").unwrap_or("fx_rates.json".to_string()),
+            statement_page_size: std::env::var("PAYMENTS_STATEMENT_PAGE_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&size| size > 0)
+                .unwrap_or(500),
+            fx_quote_ttl_secs: std::env::var("PAYMENTS_FX_QUOTE_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60),
         }
     }
 }
@@ -46,23 +201,58 @@ pub fn index() -> Response<String> {
 #[launch]
 pub async fn rocket() -> Rocket<Build> {
     dotenvy::dotenv().ok();
-    let config = AppConfig::default();
+    build(AppConfig::default()).await
+}
+
+pub async fn build(config: AppConfig) -> Rocket<Build> {
     let db = db::connect(&config).await.unwrap();
 
     Migrator::up(&db, None).await.unwrap();
 
-    // Spawn Kafka consumer task
+    ledger::report_mismatches(&db).await;
+
+    let (publisher, subscriber) = events::build(&config);
+    let processor = processing::build(&config);
+    let fx_rates = fx::build(&config);
+    let login_throttle = LoginThrottle::new(Arc::new(MemoryAttemptStore::default()), &config);
+    let mailer = mailer::build(&config);
+    let password_policy = auth::password::build(&config);
+    let keys = auth::keys::build(&config);
+
+    // Spawn event consumer task
+    let db_clone = db.clone();
+    tokio::spawn(async move {
+        events::consume(subscriber, db_clone, processor).await;
+    });
+
+    // Spawn outbox relay task
     let db_clone = db.clone();
+    let publisher_clone = publisher.clone();
     tokio::spawn(async move {
-        kafka::consumer::start(db_clone).await;
+        outbox::start(db_clone, publisher_clone).await;
     });
-    
+
+    // Spawn expired hold sweeper task
+    let db_clone = db.clone();
+    let sweep_interval = std::time::Duration::from_secs(config.hold_sweep_interval_secs);
+    tokio::spawn(async move {
+        holds::start_sweeper(db_clone, sweep_interval).await;
+    });
+
     rocket::build()
         .attach(CORS)
+        .attach(FlushPublisher)
         .manage(db)
+        .manage(publisher)
+        .manage(fx_rates)
+        .manage(login_throttle)
+        .manage(mailer)
+        .manage(password_policy)
+        .manage(keys)
         .manage(config)
         .mount("/", routes![options])
         .mount("/", routes![index])
+        .mount("/", routes![controllers::auth::jwks])
         .mount("/", routes![
             controllers::profile::get_profile,
             controllers::profile::update_profile
@@ -70,13 +260,46 @@ pub async fn rocket() -> Rocket<Build> {
         .mount("/auth", routes![
             controllers::auth::register,
             controllers::auth::login,
+            controllers::auth::refresh,
+            controllers::auth::logout,
+            controllers::auth::logout_all,
+            controllers::auth::verify_email,
+            controllers::auth::resend_verification,
+            controllers::auth::forgot_password,
+            controllers::auth::reset_password,
+            controllers::auth::change_password,
             controllers::auth::me
         ])
-        .mount("/accounts", routes![controllers::accounts::balance])
+        .mount("/auth/mfa", routes![
+            controllers::mfa::status,
+            controllers::mfa::enroll,
+            controllers::mfa::activate,
+            controllers::mfa::regenerate_recovery_codes,
+            controllers::mfa::disable,
+            controllers::mfa::verify
+        ])
+        .mount("/accounts", routes![
+            controllers::accounts::balance,
+            controllers::accounts::open_account,
+            controllers::accounts::list_accounts,
+            controllers::accounts::statement
+        ])
+        .mount("/fx", routes![controllers::fx::create_quote])
+        .mount("/admin", routes![
+            controllers::admin::search_users,
+            controllers::admin::view_account,
+            controllers::admin::view_transaction,
+            controllers::admin::freeze_account,
+            controllers::admin::unfreeze_account
+        ])
         .mount("/transactions", routes![
             controllers::transactions::create_transaction,
             controllers::transactions::get_transaction_status,
-            controllers::transactions::list_transactions
+            controllers::transactions::list_transactions,
+            controllers::transactions::refund_transaction,
+            controllers::holds::authorize,
+            controllers::holds::capture,
+            controllers::holds::void
         ])
 }
 
@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+
+use chrono::Utc;
+
+use super::{Email, MailError, Mailer};
+
+/// Writes each message to its own file in `dir`, named
+/// `<unix nanos>-<recipient>.eml`, so the latest mail for an address sorts
+/// last.
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, MailError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(|e| MailError(e.to_string()))?;
+        Ok(FileMailer { dir })
+    }
+}
+
+#[rocket::async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: Email) -> Result<(), MailError> {
+        let name = format!(
+            "{}-{}.eml",
+            Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+            email.to.replace(['/', '\\'], "_")
+        );
+        tokio::fs::write(self.dir.join(name), email.to_string())
+            .await
+            .map_err(|e| MailError(e.to_string()))
+    }
+}
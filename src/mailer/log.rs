@@ -0,0 +1,12 @@
+use super::{Email, MailError, Mailer};
+
+/// Prints each message to stdout instead of sending it.
+pub struct LogMailer;
+
+#[rocket::async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: Email) -> Result<(), MailError> {
+        println!("Mail to {}:\n{}", email.to, email);
+        Ok(())
+    }
+}
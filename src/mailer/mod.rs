@@ -0,0 +1,56 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::AppConfig;
+
+pub mod file;
+pub mod log;
+
+use file::FileMailer;
+use log::LogMailer;
+
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl fmt::Display for Email {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "From: {}\nTo: {}\nSubject: {}\n\n{}\n",
+            self.from, self.to, self.subject, self.body
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct MailError(pub String);
+
+impl fmt::Display for MailError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Delivers transactional email.
+#[rocket::async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> Result<(), MailError>;
+}
+
+/// Builds the mailer selected by `PAYMENTS_MAILER` (`log`, the default,
+/// printing each message, or `file` writing them to `PAYMENTS_MAIL_DIR`).
+pub fn build(config: &AppConfig) -> Arc<dyn Mailer> {
+    match config.mailer.as_str() {
+        "log" => Arc::new(LogMailer),
+        "file" => match FileMailer::new(&config.mail_dir) {
+            Ok(mailer) => Arc::new(mailer),
+            Err(e) => panic!("Failed to use {} for mail: {}", config.mail_dir, e),
+        },
+        other => panic!("Unknown PAYMENTS_MAILER '{}', expected 'log' or 'file'.", other),
+    }
+}
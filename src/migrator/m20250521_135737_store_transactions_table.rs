@@ -54,7 +54,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
-enum Txns {
+pub enum Txns {
     Table,
     TxnId,
     AccountId,
@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Money is stored as NUMERIC(20, 4): four fractional digits cover every
+// ISO 4217 minor unit, and the per-currency scale is enforced by
+// `utils::money::minor_units` when amounts enter the system. Every existing
+// row is INR, so legacy float values are rounded to paisa on the way over.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "ALTER TABLE account \
+                ALTER COLUMN balance TYPE NUMERIC(20, 4) USING round(balance::numeric, 2), \
+                ALTER COLUMN locked_balance TYPE NUMERIC(20, 4) USING round(locked_balance::numeric, 2)",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "ALTER TABLE txns \
+                ALTER COLUMN amount TYPE NUMERIC(20, 4) USING round(amount::numeric, 2)",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "ALTER TABLE account \
+                ALTER COLUMN balance TYPE REAL, \
+                ALTER COLUMN locked_balance TYPE REAL",
+        )
+        .await?;
+
+        db.execute_unprepared("ALTER TABLE txns ALTER COLUMN amount TYPE REAL")
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20250521_135737_store_transactions_table::Txns;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LedgerEntries::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LedgerEntries::EntryId)
+                            .string()
+                            .not_null()
+                            .primary_key()
+                            .default(Expr::cust("concat('le-', gen_random_uuid()::text)")),
+                    )
+                    .col(ColumnDef::new(LedgerEntries::TxnId).string().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-ledger_entries-txn_id")
+                            .from(LedgerEntries::Table, LedgerEntries::TxnId)
+                            .to(Txns::Table, Txns::TxnId),
+                    )
+                    // Either a user `account.account_id` or a system account
+                    // such as `clearing-INR`, so this is deliberately not a FK.
+                    .col(ColumnDef::new(LedgerEntries::AccountId).string().not_null())
+                    .col(ColumnDef::new(LedgerEntries::EntryType).string().not_null())
+                    .col(ColumnDef::new(LedgerEntries::Amount).decimal_len(20, 4).not_null())
+                    .col(ColumnDef::new(LedgerEntries::CurrencyCode).string().not_null())
+                    .col(
+                        ColumnDef::new(LedgerEntries::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-ledger_entries-account_id")
+                    .table(LedgerEntries::Table)
+                    .col(LedgerEntries::AccountId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing balances predate the ledger; book them as opening postings
+        // so the invariant check starts from a consistent state.
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "INSERT INTO ledger_entries (account_id, entry_type, amount, currency_code) \
+             SELECT account_id, 'credit', balance, currency_code FROM account \
+             UNION ALL \
+             SELECT concat('clearing-', currency_code), 'debit', balance, currency_code FROM account",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LedgerEntries::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum LedgerEntries {
+    Table,
+    EntryId,
+    TxnId,
+    AccountId,
+    EntryType,
+    Amount,
+    CurrencyCode,
+    CreatedAt,
+}
@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20250521_135328_create_users_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(IdempotencyKeys::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(IdempotencyKeys::UserId).string().not_null())
+                    .col(ColumnDef::new(IdempotencyKeys::IdempotencyKey).string().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(IdempotencyKeys::UserId)
+                            .col(IdempotencyKeys::IdempotencyKey),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-idempotency_keys-user_id")
+                            .from(IdempotencyKeys::Table, IdempotencyKeys::UserId)
+                            .to(Users::Table, Users::UserId),
+                    )
+                    .col(ColumnDef::new(IdempotencyKeys::RequestHash).string().not_null())
+                    .col(ColumnDef::new(IdempotencyKeys::ResponseStatus).small_integer().not_null())
+                    .col(ColumnDef::new(IdempotencyKeys::ResponseBody).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(IdempotencyKeys::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(IdempotencyKeys::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum IdempotencyKeys {
+    Table,
+    UserId,
+    IdempotencyKey,
+    RequestHash,
+    ResponseStatus,
+    ResponseBody,
+    CreatedAt,
+}
@@ -0,0 +1,83 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OutboxEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OutboxEvents::EventId)
+                            .string()
+                            .not_null()
+                            .primary_key()
+                            .default(Expr::cust("concat('evt-', gen_random_uuid()::text)")),
+                    )
+                    .col(ColumnDef::new(OutboxEvents::Topic).string().not_null())
+                    .col(ColumnDef::new(OutboxEvents::EventKey).string().not_null())
+                    .col(ColumnDef::new(OutboxEvents::Payload).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(OutboxEvents::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(OutboxEvents::LastError).text().null())
+                    .col(
+                        ColumnDef::new(OutboxEvents::NextAttemptAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(OutboxEvents::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(OutboxEvents::DeliveredAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-outbox_events-delivered_at-next_attempt_at")
+                    .table(OutboxEvents::Table)
+                    .col(OutboxEvents::DeliveredAt)
+                    .col(OutboxEvents::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OutboxEvents::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum OutboxEvents {
+    Table,
+    EventId,
+    Topic,
+    EventKey,
+    Payload,
+    Attempts,
+    LastError,
+    NextAttemptAt,
+    CreatedAt,
+    DeliveredAt,
+}
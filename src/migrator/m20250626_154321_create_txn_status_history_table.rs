@@ -0,0 +1,84 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20250521_135737_store_transactions_table::Txns;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TxnStatusHistory::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TxnStatusHistory::HistoryId)
+                            .string()
+                            .not_null()
+                            .primary_key()
+                            .default(Expr::cust("concat('tsh-', gen_random_uuid()::text)")),
+                    )
+                    .col(ColumnDef::new(TxnStatusHistory::TxnId).string().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-txn_status_history-txn_id")
+                            .from(TxnStatusHistory::Table, TxnStatusHistory::TxnId)
+                            .to(Txns::Table, Txns::TxnId),
+                    )
+                    .col(ColumnDef::new(TxnStatusHistory::FromStatus).string().null())
+                    .col(ColumnDef::new(TxnStatusHistory::ToStatus).string().not_null())
+                    .col(ColumnDef::new(TxnStatusHistory::ReasonCode).string().null())
+                    .col(ColumnDef::new(TxnStatusHistory::Message).text().null())
+                    .col(ColumnDef::new(TxnStatusHistory::Actor).string().not_null())
+                    .col(
+                        ColumnDef::new(TxnStatusHistory::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-txn_status_history-txn_id")
+                    .table(TxnStatusHistory::Table)
+                    .col(TxnStatusHistory::TxnId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Give existing transactions a starting point for their timeline.
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "INSERT INTO txn_status_history (txn_id, to_status, reason_code, actor, created_at) \
+             SELECT txn_id, status, failure_reason, 'migration', created_at FROM txns",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TxnStatusHistory::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TxnStatusHistory {
+    Table,
+    HistoryId,
+    TxnId,
+    FromStatus,
+    ToStatus,
+    ReasonCode,
+    Message,
+    Actor,
+    CreatedAt,
+}
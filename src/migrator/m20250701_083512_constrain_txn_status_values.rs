@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const STATUSES: &str =
+    "'pending', 'processing', 'succeeded', 'failed', 'reversed', 'cancelled', 'expired'";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared("UPDATE txns SET status = 'succeeded' WHERE status = 'success'")
+            .await?;
+        db.execute_unprepared(
+            "UPDATE txn_status_history SET to_status = 'succeeded' WHERE to_status = 'success'",
+        )
+        .await?;
+        db.execute_unprepared(
+            "UPDATE txn_status_history SET from_status = 'succeeded' WHERE from_status = 'success'",
+        )
+        .await?;
+
+        db.execute_unprepared(&format!(
+            "ALTER TABLE txns ADD CONSTRAINT chk_txns_status CHECK (status IN ({}))",
+            STATUSES
+        ))
+        .await?;
+        db.execute_unprepared(&format!(
+            "ALTER TABLE txn_status_history \
+                ADD CONSTRAINT chk_txn_status_history_to_status CHECK (to_status IN ({0})), \
+                ADD CONSTRAINT chk_txn_status_history_from_status CHECK (from_status IN ({0}))",
+            STATUSES
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "ALTER TABLE txn_status_history \
+                DROP CONSTRAINT chk_txn_status_history_to_status, \
+                DROP CONSTRAINT chk_txn_status_history_from_status",
+        )
+        .await?;
+        db.execute_unprepared("ALTER TABLE txns DROP CONSTRAINT chk_txns_status")
+            .await?;
+        db.execute_unprepared("UPDATE txns SET status = 'success' WHERE status = 'succeeded'")
+            .await?;
+        db.execute_unprepared(
+            "UPDATE txn_status_history SET to_status = 'success' WHERE to_status = 'succeeded'",
+        )
+        .await?;
+        db.execute_unprepared(
+            "UPDATE txn_status_history SET from_status = 'success' WHERE from_status = 'succeeded'",
+        )
+        .await?;
+
+        Ok(())
+    }
+}
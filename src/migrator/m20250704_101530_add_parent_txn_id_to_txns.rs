@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Txns::Table)
+                    .add_column(ColumnDef::new(Txns::ParentTxnId).string().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk-txns-parent_txn_id")
+                            .from_tbl(Txns::Table)
+                            .from_col(Txns::ParentTxnId)
+                            .to_tbl(Txns::Table)
+                            .to_col(Txns::TxnId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-txns-parent_txn_id")
+                    .table(Txns::Table)
+                    .col(Txns::ParentTxnId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-txns-parent_txn_id")
+                    .table(Txns::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Txns::Table)
+                    .drop_foreign_key(Alias::new("fk-txns-parent_txn_id"))
+                    .drop_column(Txns::ParentTxnId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Txns {
+    Table,
+    TxnId,
+    ParentTxnId,
+}
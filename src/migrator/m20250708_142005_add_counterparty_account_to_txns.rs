@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20250521_135711_create_accounts_table::Account;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Txns::Table)
+                    .add_column(ColumnDef::new(Txns::CounterpartyAccountId).string().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk-txns-counterparty_account_id")
+                            .from_tbl(Txns::Table)
+                            .from_col(Txns::CounterpartyAccountId)
+                            .to_tbl(Account::Table)
+                            .to_col(Account::AccountId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Txns::Table)
+                    .drop_foreign_key(Alias::new("fk-txns-counterparty_account_id"))
+                    .drop_column(Txns::CounterpartyAccountId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Txns {
+    Table,
+    CounterpartyAccountId,
+}
@@ -0,0 +1,99 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20250521_135711_create_accounts_table::Account;
+use super::m20250521_135737_store_transactions_table::Txns;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Holds::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Holds::HoldId)
+                            .string()
+                            .not_null()
+                            .primary_key()
+                            .default(Expr::cust("concat('hold-', gen_random_uuid()::text)")),
+                    )
+                    .col(ColumnDef::new(Holds::AccountId).string().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-holds-account_id")
+                            .from(Holds::Table, Holds::AccountId)
+                            .to(Account::Table, Account::AccountId),
+                    )
+                    .col(ColumnDef::new(Holds::Amount).decimal_len(20, 4).not_null())
+                    .col(ColumnDef::new(Holds::CurrencyCode).string().not_null())
+                    .col(
+                        ColumnDef::new(Holds::Status)
+                            .string()
+                            .not_null()
+                            .default("active")
+                            .check(Expr::col(Holds::Status).is_in(["active", "captured", "voided", "expired"])),
+                    )
+                    .col(ColumnDef::new(Holds::CaptureTxnId).string().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-holds-capture_txn_id")
+                            .from(Holds::Table, Holds::CaptureTxnId)
+                            .to(Txns::Table, Txns::TxnId),
+                    )
+                    .col(
+                        ColumnDef::new(Holds::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Holds::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Holds::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-holds-status-expires_at")
+                    .table(Holds::Table)
+                    .col(Holds::Status)
+                    .col(Holds::ExpiresAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Holds::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Holds {
+    Table,
+    HoldId,
+    AccountId,
+    Amount,
+    CurrencyCode,
+    Status,
+    CaptureTxnId,
+    ExpiresAt,
+    CreatedAt,
+    UpdatedAt,
+}
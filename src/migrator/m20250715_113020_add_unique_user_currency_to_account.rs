@@ -0,0 +1,34 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20250521_135711_create_accounts_table::Account;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-account-user_id-currency_code")
+                    .table(Account::Table)
+                    .col(Account::UserId)
+                    .col(Account::CurrencyCode)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-account-user_id-currency_code")
+                    .table(Account::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,92 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20250521_135328_create_users_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(FxQuotes::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FxQuotes::QuoteId)
+                            .string()
+                            .not_null()
+                            .primary_key()
+                            .default(Expr::cust("concat('fxq-', gen_random_uuid()::text)")),
+                    )
+                    .col(ColumnDef::new(FxQuotes::UserId).string().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-fx_quotes-user_id")
+                            .from(FxQuotes::Table, FxQuotes::UserId)
+                            .to(Users::Table, Users::UserId),
+                    )
+                    .col(ColumnDef::new(FxQuotes::FromCurrency).string().not_null())
+                    .col(ColumnDef::new(FxQuotes::ToCurrency).string().not_null())
+                    .col(ColumnDef::new(FxQuotes::Rate).decimal_len(20, 10).not_null())
+                    .col(
+                        ColumnDef::new(FxQuotes::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(FxQuotes::UsedAt).timestamp_with_time_zone().null())
+                    .col(
+                        ColumnDef::new(FxQuotes::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Txns::Table)
+                    .add_column(ColumnDef::new(Txns::FxRate).decimal_len(20, 10).null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Txns::Table)
+                    .drop_column(Txns::FxRate)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(FxQuotes::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum FxQuotes {
+    Table,
+    QuoteId,
+    UserId,
+    FromCurrency,
+    ToCurrency,
+    Rate,
+    ExpiresAt,
+    UsedAt,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Txns {
+    Table,
+    FxRate,
+}
@@ -0,0 +1,33 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20250521_135737_store_transactions_table::Txns;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-txns-account_id-created_at")
+                    .table(Txns::Table)
+                    .col(Txns::AccountId)
+                    .col(Txns::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-txns-account_id-created_at")
+                    .table(Txns::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LoginLockouts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LoginLockouts::LockoutId)
+                            .string()
+                            .not_null()
+                            .primary_key()
+                            .default(Expr::cust("concat('lock-', gen_random_uuid()::text)")),
+                    )
+                    .col(ColumnDef::new(LoginLockouts::Subject).string().not_null())
+                    .col(ColumnDef::new(LoginLockouts::Email).string().null())
+                    .col(ColumnDef::new(LoginLockouts::IpAddress).string().null())
+                    .col(ColumnDef::new(LoginLockouts::Failures).integer().not_null())
+                    .col(
+                        ColumnDef::new(LoginLockouts::LockedUntil)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(LoginLockouts::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-login_lockouts-subject-created_at")
+                    .table(LoginLockouts::Table)
+                    .col(LoginLockouts::Subject)
+                    .col(LoginLockouts::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LoginLockouts::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum LoginLockouts {
+    Table,
+    LockoutId,
+    Subject,
+    Email,
+    IpAddress,
+    Failures,
+    LockedUntil,
+    CreatedAt,
+}
@@ -0,0 +1,108 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(ColumnDef::new(Users::MfaSecret).string().null())
+                    .add_column(
+                        ColumnDef::new(Users::MfaEnabled)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .add_column(ColumnDef::new(Users::MfaLastStep).big_integer().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(MfaRecoveryCodes::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MfaRecoveryCodes::CodeId)
+                            .string()
+                            .not_null()
+                            .primary_key()
+                            .default(Expr::cust("concat('rc-', gen_random_uuid()::text)")),
+                    )
+                    .col(ColumnDef::new(MfaRecoveryCodes::UserId).string().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-mfa_recovery_codes-user_id")
+                            .from(MfaRecoveryCodes::Table, MfaRecoveryCodes::UserId)
+                            .to(Users::Table, Users::UserId),
+                    )
+                    .col(ColumnDef::new(MfaRecoveryCodes::CodeHash).string().not_null())
+                    .col(
+                        ColumnDef::new(MfaRecoveryCodes::UsedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(MfaRecoveryCodes::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-mfa_recovery_codes-user_id-code_hash")
+                    .table(MfaRecoveryCodes::Table)
+                    .col(MfaRecoveryCodes::UserId)
+                    .col(MfaRecoveryCodes::CodeHash)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MfaRecoveryCodes::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::MfaSecret)
+                    .drop_column(Users::MfaEnabled)
+                    .drop_column(Users::MfaLastStep)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    UserId,
+    MfaSecret,
+    MfaEnabled,
+    MfaLastStep,
+}
+
+#[derive(DeriveIden)]
+enum MfaRecoveryCodes {
+    Table,
+    CodeId,
+    UserId,
+    CodeHash,
+    UsedAt,
+    CreatedAt,
+}
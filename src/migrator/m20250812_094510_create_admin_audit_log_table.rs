@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20250521_135328_create_users_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AdminAuditLog::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AdminAuditLog::AuditId)
+                            .string()
+                            .not_null()
+                            .primary_key()
+                            .default(Expr::cust("concat('audit-', gen_random_uuid()::text)")),
+                    )
+                    .col(ColumnDef::new(AdminAuditLog::StaffUserId).string().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-admin_audit_log-staff_user_id")
+                            .from(AdminAuditLog::Table, AdminAuditLog::StaffUserId)
+                            .to(Users::Table, Users::UserId),
+                    )
+                    .col(ColumnDef::new(AdminAuditLog::Action).string().not_null())
+                    .col(ColumnDef::new(AdminAuditLog::Target).string().not_null())
+                    .col(
+                        ColumnDef::new(AdminAuditLog::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-admin_audit_log-target-created_at")
+                    .table(AdminAuditLog::Table)
+                    .col(AdminAuditLog::Target)
+                    .col(AdminAuditLog::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AdminAuditLog::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AdminAuditLog {
+    Table,
+    AuditId,
+    StaffUserId,
+    Action,
+    Target,
+    CreatedAt,
+}
@@ -3,6 +3,25 @@ pub use sea_orm_migration::prelude::*;
 mod m20250521_135328_create_users_table;
 mod m20250521_135711_create_accounts_table;
 mod m20250521_135737_store_transactions_table;
+mod m20250610_091204_convert_money_columns_to_numeric;
+mod m20250612_104530_create_ledger_entries_table;
+mod m20250616_142210_create_idempotency_keys_table;
+mod m20250619_093015_create_outbox_events_table;
+mod m20250623_110842_add_account_status_and_failure_reason;
+mod m20250626_154321_create_txn_status_history_table;
+mod m20250701_083512_constrain_txn_status_values;
+mod m20250704_101530_add_parent_txn_id_to_txns;
+mod m20250708_142005_add_counterparty_account_to_txns;
+mod m20250711_093740_create_holds_table;
+mod m20250715_113020_add_unique_user_currency_to_account;
+mod m20250718_160245_create_fx_quotes_table;
+mod m20250722_104815_add_txns_account_created_at_index;
+mod m20250725_091530_add_role_to_users;
+mod m20250729_140210_create_refresh_tokens_table;
+mod m20250801_101245_create_login_lockouts_table;
+mod m20250805_093320_add_mfa_to_users;
+mod m20250808_142730_add_email_verified_to_users;
+mod m20250812_094510_create_admin_audit_log_table;
 
 pub struct Migrator;
 
@@ -13,6 +32,25 @@ impl MigratorTrait for Migrator {
             Box::new(m20250521_135328_create_users_table::Migration),
             Box::new(m20250521_135711_create_accounts_table::Migration),
             Box::new(m20250521_135737_store_transactions_table::Migration),
+            Box::new(m20250610_091204_convert_money_columns_to_numeric::Migration),
+            Box::new(m20250612_104530_create_ledger_entries_table::Migration),
+            Box::new(m20250616_142210_create_idempotency_keys_table::Migration),
+            Box::new(m20250619_093015_create_outbox_events_table::Migration),
+            Box::new(m20250623_110842_add_account_status_and_failure_reason::Migration),
+            Box::new(m20250626_154321_create_txn_status_history_table::Migration),
+            Box::new(m20250701_083512_constrain_txn_status_values::Migration),
+            Box::new(m20250704_101530_add_parent_txn_id_to_txns::Migration),
+            Box::new(m20250708_142005_add_counterparty_account_to_txns::Migration),
+            Box::new(m20250711_093740_create_holds_table::Migration),
+            Box::new(m20250715_113020_add_unique_user_currency_to_account::Migration),
+            Box::new(m20250718_160245_create_fx_quotes_table::Migration),
+            Box::new(m20250722_104815_add_txns_account_created_at_index::Migration),
+            Box::new(m20250725_091530_add_role_to_users::Migration),
+            Box::new(m20250729_140210_create_refresh_tokens_table::Migration),
+            Box::new(m20250801_101245_create_login_lockouts_table::Migration),
+            Box::new(m20250805_093320_add_mfa_to_users::Migration),
+            Box::new(m20250808_142730_add_email_verified_to_users::Migration),
+            Box::new(m20250812_094510_create_admin_audit_log_table::Migration),
         ]
     }
 }
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{FixedOffset, Utc};
+use sea_orm::sea_query::{Expr, LockBehavior, LockType};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::*;
+
+use crate::entities::{outbox_events, prelude::OutboxEvents};
+use crate::events::EventPublisher;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const BATCH_SIZE: u64 = 50;
+const MAX_BACKOFF_SECS: i64 = 300;
+/// How long a claimed batch is hidden from other relays. It must outlast
+/// publishing a whole batch; if a relay dies mid-batch, its rows are picked
+/// up again once it runs out, so delivery is at-least-once.
+const CLAIM_LEASE_SECS: i64 = 600;
+
+/// Queues an event for publishing. Call this with the same database
+/// transaction that writes the state the event describes, so the event is
+/// recorded if and only if that state is committed.
+pub async fn enqueue<C: ConnectionTrait>(
+    db: &C,
+    topic: &str,
+    key: &str,
+    payload: serde_json::Value,
+) -> Result<(), DbErr> {
+    let now = now();
+
+    OutboxEvents::insert(outbox_events::ActiveModel {
+        topic: Set(topic.to_string()),
+        event_key: Set(key.to_string()),
+        payload: Set(payload),
+        attempts: Set(0),
+        next_attempt_at: Set(now),
+        created_at: Set(now),
+        ..Default::default()
+    })
+    .exec_without_returning(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Relay loop: publishes undelivered outbox rows in creation order and marks
+/// them delivered. Failed sends are retried with exponential backoff.
+pub async fn start(db: DatabaseConnection, publisher: Arc<dyn EventPublisher>) {
+    loop {
+        match relay_batch(&db, publisher.as_ref()).await {
+            Ok(0) => tokio::time::sleep(POLL_INTERVAL).await,
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Outbox relay error: {}", e);
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+fn now() -> DateTimeWithTimeZone {
+    Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap())
+}
+
+/// Claims a batch of due rows by counting the attempt and pushing
+/// `next_attempt_at` out by `CLAIM_LEASE_SECS`, then commits. The row locks
+/// are only held for that update; publishing happens outside any database
+/// transaction so a slow broker does not pin a connection or the rows.
+async fn claim_batch(db: &DatabaseConnection) -> Result<Vec<outbox_events::Model>, DbErr> {
+    let db_txn = db.begin().await?;
+    let now = now();
+
+    // SKIP LOCKED lets several relay instances claim rows concurrently
+    // without picking the same ones.
+    let events = OutboxEvents::find()
+        .filter(outbox_events::Column::DeliveredAt.is_null())
+        .filter(outbox_events::Column::NextAttemptAt.lte(now))
+        .order_by_asc(outbox_events::Column::CreatedAt)
+        .limit(BATCH_SIZE)
+        .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+        .all(&db_txn)
+        .await?;
+
+    if !events.is_empty() {
+        OutboxEvents::update_many()
+            .col_expr(
+                outbox_events::Column::Attempts,
+                Expr::col(outbox_events::Column::Attempts).add(1),
+            )
+            .col_expr(
+                outbox_events::Column::NextAttemptAt,
+                Expr::value(now + chrono::Duration::seconds(CLAIM_LEASE_SECS)),
+            )
+            .filter(outbox_events::Column::EventId.is_in(events.iter().map(|e| e.event_id.clone())))
+            .exec(&db_txn)
+            .await?;
+    }
+
+    db_txn.commit().await?;
+
+    Ok(events)
+}
+
+async fn relay_batch(db: &DatabaseConnection, publisher: &dyn EventPublisher) -> Result<usize, DbErr> {
+    let events = claim_batch(db).await?;
+    if events.is_empty() {
+        return Ok(0);
+    }
+
+    let mut results = Vec::with_capacity(events.len());
+    for event in events {
+        let result = publisher
+            .publish(&event.topic, &event.event_key, &event.payload.to_string())
+            .await;
+        results.push((event, result));
+    }
+
+    let db_txn = db.begin().await?;
+    let now = now();
+
+    let mut delivered = 0;
+    for (event, result) in results {
+        let attempts = event.attempts + 1;
+        let mut event_am: outbox_events::ActiveModel = event.into();
+        event_am.attempts = Set(attempts);
+
+        match result {
+            Ok(()) => {
+                event_am.delivered_at = Set(Some(now));
+                event_am.last_error = Set(None);
+                delivered += 1;
+            }
+            Err(err) => {
+                eprintln!("Failed to publish outbox event: {}", err);
+                let backoff = 2_i64.saturating_pow(attempts as u32).min(MAX_BACKOFF_SECS);
+                event_am.next_attempt_at = Set(now + chrono::Duration::seconds(backoff));
+                event_am.last_error = Set(Some(err.to_string()));
+            }
+        }
+
+        event_am.update(&db_txn).await?;
+    }
+
+    db_txn.commit().await?;
+
+    Ok(delivered)
+}
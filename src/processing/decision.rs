@@ -0,0 +1,48 @@
+use crate::entities::{account, txns};
+
+/// Reason codes persisted on `txns.failure_reason` when a transaction is
+/// declined.
+pub mod reason {
+    pub const INSUFFICIENT_FUNDS: &str = "insufficient_funds";
+    pub const AMOUNT_OVER_LIMIT: &str = "amount_over_limit";
+    pub const ACCOUNT_INACTIVE: &str = "account_inactive";
+    pub const UNSUPPORTED_TXN_TYPE: &str = "unsupported_txn_type";
+    pub const GATEWAY_DECLINED: &str = "gateway_declined";
+    pub const GATEWAY_TIMEOUT: &str = "gateway_timeout";
+    pub const ISSUER_UNAVAILABLE: &str = "issuer_unavailable";
+    pub const SUSPECTED_FRAUD: &str = "suspected_fraud";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Approved,
+    Declined {
+        reason_code: &'static str,
+        message: String,
+    },
+}
+
+impl Decision {
+    pub fn declined(reason_code: &'static str, message: impl Into<String>) -> Self {
+        Decision::Declined {
+            reason_code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Decides whether a pending transaction should settle.
+#[rocket::async_trait]
+pub trait TransactionProcessor: Send + Sync {
+    /// Asks the provider for its decision before any row is locked, so a
+    /// slow gateway call does not hold up other settlements on the account.
+    /// `account` is an unlocked read; `decide` re-checks it under the lock.
+    async fn authorize(&self, _txn: &txns::Model, _account: &account::Model) -> Decision {
+        Decision::Approved
+    }
+
+    /// Called by the consumer while the txn and account rows are locked,
+    /// so implementations see the balance the settlement will be applied
+    /// to. Must not wait on anything external.
+    async fn decide(&self, txn: &txns::Model, account: &account::Model) -> Decision;
+}
@@ -0,0 +1,361 @@
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{FixedOffset, Utc};
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::Deserialize;
+
+use crate::AppConfig;
+use crate::entities::account::{self, Entity as Accounts};
+use crate::entities::txns::{self, Entity as Txns};
+use crate::fx;
+use crate::ledger;
+use crate::refunds;
+use crate::txn_status::{self, Transition, TxnStatus};
+
+pub mod decision;
+pub mod rules;
+pub mod simulated;
+
+pub use decision::{Decision, TransactionProcessor};
+use rules::RulesProcessor;
+use simulated::SimulatedGateway;
+
+/// Body of a message on the transaction events topic.
+#[derive(Debug, Deserialize)]
+pub struct TransactionEvent {
+    pub txn_id: String,
+    pub account_id: String,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub amount: Decimal,
+    pub txn_type: String
+}
+
+/// Builds the processor selected by `PAYMENTS_PROCESSOR` (`rules`, the
+/// default, or `simulated`).
+pub fn build(config: &AppConfig) -> Arc<dyn TransactionProcessor> {
+    let rules = RulesProcessor {
+        max_txn_amount: config.max_txn_amount,
+    };
+
+    match config.processor.as_str() {
+        "rules" => Arc::new(rules),
+        "simulated" => Arc::new(SimulatedGateway::new(
+            rules,
+            config.sim_failure_rate,
+            Duration::from_millis(config.sim_latency_ms),
+            config.sim_seed,
+        )),
+        other => panic!("Unknown PAYMENTS_PROCESSOR '{}', expected 'rules' or 'simulated'.", other),
+    }
+}
+
+pub async fn handle_transaction(
+    txn: TransactionEvent,
+    db: DatabaseConnection,
+    processor: Arc<dyn TransactionProcessor>,
+) {
+    println!("Processing transaction: {}", txn.txn_id);
+
+    match process_transaction(&txn, &db, processor.as_ref()).await {
+        Ok(Some(status)) => println!("Transaction {} marked as {}", txn.txn_id, status),
+        Ok(None) => {}
+        Err(e) => eprintln!("Failed to process transaction {}: {}", txn.txn_id, e),
+    }
+}
+
+/// Settles a single transaction. The processor's `authorize` runs first,
+/// without any locks held. The txn is then claimed with a compare-and-set
+/// from `pending` to `processing`, so a redelivered event for a transaction
+/// that has already been handled is a no-op. The account row is then locked
+/// with `SELECT ... FOR UPDATE`, `decide` re-checks it, and the final status
+/// and balance mutation commit together, so concurrent purchases against
+/// one account are applied one at a time and can never overdraw it.
+async fn process_transaction(
+    txn: &TransactionEvent,
+    db: &DatabaseConnection,
+    processor: &dyn TransactionProcessor,
+) -> Result<Option<TxnStatus>, DbErr> {
+    let authorized = match authorize(txn, db, processor).await? {
+        Some(decision) => decision,
+        None => {
+            println!("Transaction {} is not pending, skipping", txn.txn_id);
+            return Ok(None);
+        }
+    };
+
+    let db_txn = db.begin().await?;
+
+    let claimed = txn_status::advance(
+        &db_txn,
+        Transition {
+            txn_id: &txn.txn_id,
+            from: Some(TxnStatus::Pending),
+            to: TxnStatus::Processing,
+            reason_code: None,
+            message: None,
+            actor: txn_status::ACTOR_PROCESSOR,
+        },
+    )
+    .await?;
+
+    if !claimed {
+        println!("Transaction {} is not pending, skipping", txn.txn_id);
+        return Ok(None);
+    }
+
+    let txn_model = match Txns::find()
+        .filter(txns::Column::TxnId.eq(txn.txn_id.clone()))
+        .one(&db_txn)
+        .await?
+    {
+        Some(txn_model) => txn_model,
+        None => {
+            eprintln!("No transaction found in DB for txn_id: {}", txn.txn_id);
+            return Ok(None);
+        }
+    };
+
+    // A refund locks the purchase it refunds before the account, the same
+    // order `refund_transaction` uses, so the full-refund check below sees
+    // every other settled refund.
+    let parent = match &txn_model.parent_txn_id {
+        Some(parent_txn_id) => {
+            Txns::find_by_id(parent_txn_id.clone())
+                .lock_exclusive()
+                .one(&db_txn)
+                .await?
+        }
+        None => None,
+    };
+
+    // A transfer locks both accounts. Taking the row locks in account id
+    // order keeps two opposite transfers between the same pair of accounts
+    // from deadlocking.
+    let mut account_ids = vec![txn_model.account_id.clone()];
+    account_ids.extend(txn_model.counterparty_account_id.clone());
+    let mut locked = Accounts::find()
+        .filter(account::Column::AccountId.is_in(account_ids))
+        .order_by_asc(account::Column::AccountId)
+        .lock_exclusive()
+        .all(&db_txn)
+        .await?;
+
+    let account_model = match take_account(&mut locked, &txn_model.account_id) {
+        Some(account_model) => account_model,
+        None => {
+            eprintln!("Account {} not found", txn_model.account_id);
+            return Ok(None);
+        }
+    };
+    let counterparty = match &txn_model.counterparty_account_id {
+        Some(counterparty_id) => match take_account(&mut locked, counterparty_id) {
+            Some(counterparty) => Some(counterparty),
+            None => {
+                eprintln!("Counterparty account {} not found", counterparty_id);
+                return Ok(None);
+            }
+        },
+        None => None,
+    };
+
+    let decision = match &counterparty {
+        Some(recipient) if recipient.status != "active" => Decision::declined(
+            decision::reason::ACCOUNT_INACTIVE,
+            format!("Recipient account {} is {}", recipient.account_id, recipient.status),
+        ),
+        _ if authorized != Decision::Approved => authorized,
+        _ => processor.decide(&txn_model, &account_model).await,
+    };
+
+    let (new_status, reason_code, message) = match decision {
+        Decision::Approved => {
+            match counterparty {
+                Some(recipient) => settle_transfer(&txn_model, account_model, recipient, &db_txn).await?,
+                None => update_account_balance(&txn_model, account_model, &db_txn).await?,
+            }
+            (TxnStatus::Succeeded, None, None)
+        }
+        Decision::Declined { reason_code, message } => {
+            println!("Transaction {} declined: {} ({})", txn.txn_id, message, reason_code);
+            (TxnStatus::Failed, Some(reason_code), Some(message))
+        }
+    };
+
+    txn_status::advance(
+        &db_txn,
+        Transition {
+            txn_id: &txn_model.txn_id,
+            from: Some(TxnStatus::Processing),
+            to: new_status,
+            reason_code,
+            message,
+            actor: txn_status::ACTOR_PROCESSOR,
+        },
+    )
+    .await?;
+
+    if new_status == TxnStatus::Succeeded
+        && let Some(parent) = &parent
+    {
+        refunds::reverse_if_fully_refunded(&db_txn, parent, &txn_model.txn_id).await?;
+    }
+
+    db_txn.commit().await?;
+
+    Ok(Some(new_status))
+}
+
+/// The processor's up-front decision on a pending txn, or `None` if the txn
+/// is no longer pending. Reads without locking; the settlement re-checks
+/// everything once the rows are locked.
+async fn authorize(
+    txn: &TransactionEvent,
+    db: &DatabaseConnection,
+    processor: &dyn TransactionProcessor,
+) -> Result<Option<Decision>, DbErr> {
+    let txn_model = match Txns::find_by_id(txn.txn_id.clone()).one(db).await? {
+        Some(txn_model) if txn_model.status == TxnStatus::Pending => txn_model,
+        _ => return Ok(None),
+    };
+
+    // A missing account is reported once the settlement looks it up.
+    Ok(Some(match Accounts::find_by_id(txn_model.account_id.clone()).one(db).await? {
+        Some(account_model) => processor.authorize(&txn_model, &account_model).await,
+        None => Decision::Approved,
+    }))
+}
+
+async fn update_account_balance(
+    txn: &txns::Model,
+    account_model: account::Model,
+    db: &DatabaseTransaction,
+) -> Result<(), DbErr> {
+    let clearing = ledger::clearing_account(&txn.currency_code);
+    let (updated_balance, debit_account, credit_account) = match txn.txn_type.as_str() {
+        "credit" | refunds::REFUND => (
+            account_model.balance + txn.amount,
+            clearing,
+            account_model.account_id.clone(),
+        ),
+        "purchase" => (
+            account_model.balance - txn.amount,
+            account_model.account_id.clone(),
+            clearing,
+        ),
+        _ => return Ok(()),
+    };
+
+    ledger::post(
+        db,
+        Some(txn.txn_id.clone()),
+        debit_account,
+        credit_account,
+        txn.amount,
+        &txn.currency_code,
+    )
+    .await?;
+
+    let mut account_am = account_model.into_active_model();
+    account_am.balance = Set(updated_balance);
+    account_am.update(db).await?;
+
+    Ok(())
+}
+
+fn take_account(accounts: &mut Vec<account::Model>, account_id: &str) -> Option<account::Model> {
+    let index = accounts.iter().position(|acc| acc.account_id == account_id)?;
+    Some(accounts.swap_remove(index))
+}
+
+/// Moves a transfer or exchange from the sender's account to the
+/// counterparty and gives the counterparty a settled `transfer_in` or
+/// `exchange_in` txn linked to the sender's txn. An exchange credits the
+/// amount converted at the rate locked on the txn; each currency leg then
+/// balances against its own clearing account.
+async fn settle_transfer(
+    txn: &txns::Model,
+    sender: account::Model,
+    recipient: account::Model,
+    db: &DatabaseTransaction,
+) -> Result<(), DbErr> {
+    let (incoming_type, label, incoming_amount) = match (txn.txn_type.as_str(), txn.fx_rate) {
+        ("exchange", Some(rate)) => (
+            "exchange_in",
+            "Exchange",
+            fx::convert(txn.amount, rate, &recipient.currency_code),
+        ),
+        _ => ("transfer_in", "Transfer", txn.amount),
+    };
+
+    if sender.currency_code == recipient.currency_code {
+        ledger::post(
+            db,
+            Some(txn.txn_id.clone()),
+            sender.account_id.clone(),
+            recipient.account_id.clone(),
+            txn.amount,
+            &txn.currency_code,
+        )
+        .await?;
+    } else {
+        ledger::post(
+            db,
+            Some(txn.txn_id.clone()),
+            sender.account_id.clone(),
+            ledger::clearing_account(&sender.currency_code),
+            txn.amount,
+            &sender.currency_code,
+        )
+        .await?;
+        ledger::post(
+            db,
+            Some(txn.txn_id.clone()),
+            ledger::clearing_account(&recipient.currency_code),
+            recipient.account_id.clone(),
+            incoming_amount,
+            &recipient.currency_code,
+        )
+        .await?;
+    }
+
+    let incoming = txns::ActiveModel {
+        account_id: Set(recipient.account_id.clone()),
+        amount: Set(incoming_amount),
+        currency_code: Set(recipient.currency_code.clone()),
+        txn_type: Set(incoming_type.to_string()),
+        status: Set(TxnStatus::Succeeded),
+        created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap())),
+        parent_txn_id: Set(Some(txn.txn_id.clone())),
+        counterparty_account_id: Set(Some(sender.account_id.clone())),
+        fx_rate: Set(txn.fx_rate),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    txn_status::record(
+        db,
+        Transition {
+            txn_id: &incoming.txn_id,
+            from: None,
+            to: TxnStatus::Succeeded,
+            reason_code: None,
+            message: Some(format!("{} from {}", label, sender.account_id)),
+            actor: txn_status::ACTOR_PROCESSOR,
+        },
+    )
+    .await?;
+
+    let sender_balance = sender.balance - txn.amount;
+    let mut sender_am = sender.into_active_model();
+    sender_am.balance = Set(sender_balance);
+    sender_am.update(db).await?;
+
+    let recipient_balance = recipient.balance + incoming_amount;
+    let mut recipient_am = recipient.into_active_model();
+    recipient_am.balance = Set(recipient_balance);
+    recipient_am.update(db).await?;
+
+    Ok(())
+}
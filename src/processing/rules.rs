@@ -0,0 +1,48 @@
+use rust_decimal::Decimal;
+
+use super::decision::{Decision, TransactionProcessor, reason};
+use crate::entities::{account, txns};
+use crate::refunds;
+
+/// Default processor: approves every transaction that passes the account
+/// state, per-transaction limit and sufficient-funds checks.
+pub struct RulesProcessor {
+    pub max_txn_amount: Decimal,
+}
+
+impl RulesProcessor {
+    pub fn check(&self, txn: &txns::Model, account: &account::Model) -> Decision {
+        if account.status != "active" {
+            return Decision::declined(
+                reason::ACCOUNT_INACTIVE,
+                format!("Account {} is {}", account.account_id, account.status),
+            );
+        }
+
+        if txn.amount > self.max_txn_amount {
+            return Decision::declined(
+                reason::AMOUNT_OVER_LIMIT,
+                format!("Amount exceeds the per-transaction limit of {}", self.max_txn_amount),
+            );
+        }
+
+        match txn.txn_type.as_str() {
+            // Funds reserved by active holds are not available to spend.
+            "purchase" | "transfer" | "exchange" if account.balance - account.locked_balance < txn.amount => {
+                Decision::declined(reason::INSUFFICIENT_FUNDS, "Insufficient balance")
+            }
+            "purchase" | "credit" | refunds::REFUND | "transfer" | "exchange" => Decision::Approved,
+            other => Decision::declined(
+                reason::UNSUPPORTED_TXN_TYPE,
+                format!("Unsupported transaction type '{}'", other),
+            ),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl TransactionProcessor for RulesProcessor {
+    async fn decide(&self, txn: &txns::Model, account: &account::Model) -> Decision {
+        self.check(txn, account)
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::decision::{Decision, TransactionProcessor, reason};
+use super::rules::RulesProcessor;
+use crate::entities::{account, txns};
+
+const GATEWAY_FAILURES: [(&str, &str); 4] = [
+    (reason::GATEWAY_DECLINED, "Declined by payment gateway"),
+    (reason::GATEWAY_TIMEOUT, "Payment gateway timed out"),
+    (reason::ISSUER_UNAVAILABLE, "Card issuer unavailable"),
+    (reason::SUSPECTED_FRAUD, "Flagged as suspected fraud"),
+];
+
+/// Stands in for an external payment gateway: applies the rules first, then
+/// waits `latency` and declines a `failure_rate` fraction of the remaining
+/// transactions. The wait happens in `authorize`, before the settlement
+/// locks are taken. The RNG is seeded so a run can be reproduced exactly.
+pub struct SimulatedGateway {
+    rules: RulesProcessor,
+    failure_rate: f64,
+    latency: Duration,
+    rng: Mutex<StdRng>,
+}
+
+impl SimulatedGateway {
+    pub fn new(rules: RulesProcessor, failure_rate: f64, latency: Duration, seed: u64) -> Self {
+        Self {
+            rules,
+            failure_rate: failure_rate.clamp(0.0, 1.0),
+            latency,
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl TransactionProcessor for SimulatedGateway {
+    async fn authorize(&self, txn: &txns::Model, account: &account::Model) -> Decision {
+        let decision = self.rules.check(txn, account);
+        if decision != Decision::Approved {
+            return decision;
+        }
+
+        tokio::time::sleep(self.latency).await;
+
+        let failure = {
+            let mut rng = self.rng.lock().unwrap();
+            if rng.random_bool(self.failure_rate) {
+                Some(GATEWAY_FAILURES[rng.random_range(0..GATEWAY_FAILURES.len())])
+            } else {
+                None
+            }
+        };
+
+        match failure {
+            Some((reason_code, message)) => Decision::declined(reason_code, message),
+            None => Decision::Approved,
+        }
+    }
+
+    /// The balance may have moved while the gateway was deciding, so the
+    /// rules run again against the locked account.
+    async fn decide(&self, txn: &txns::Model, account: &account::Model) -> Decision {
+        self.rules.check(txn, account)
+    }
+}
@@ -0,0 +1,57 @@
+use rust_decimal::Decimal;
+use sea_orm::*;
+
+use crate::entities::{prelude::Txns, txns};
+use crate::txn_status::{self, Transition, TxnStatus};
+
+pub const REFUND: &str = "refund";
+
+/// Refunds that still count against the original purchase: settled ones and
+/// ones that may yet settle. A failed refund frees its amount again.
+pub const OUTSTANDING: [TxnStatus; 3] = [TxnStatus::Pending, TxnStatus::Processing, TxnStatus::Succeeded];
+
+/// Sum of the refunds of `parent_txn_id` that are in one of `statuses`.
+pub async fn refunded_total<C: ConnectionTrait>(
+    db: &C,
+    parent_txn_id: &str,
+    statuses: &[TxnStatus],
+) -> Result<Decimal, DbErr> {
+    let total: Option<Option<Decimal>> = Txns::find()
+        .select_only()
+        .column_as(txns::Column::Amount.sum(), "total")
+        .filter(txns::Column::ParentTxnId.eq(parent_txn_id))
+        .filter(txns::Column::TxnType.eq(REFUND))
+        .filter(txns::Column::Status.is_in(statuses.iter().copied()))
+        .into_tuple()
+        .one(db)
+        .await?;
+
+    Ok(total.flatten().unwrap_or(Decimal::ZERO))
+}
+
+/// Moves `parent` to `reversed` once its settled refunds add up to its full
+/// amount. The caller must hold a lock on the `parent` row so two refunds
+/// settling at once cannot both miss the final total.
+pub async fn reverse_if_fully_refunded<C: ConnectionTrait>(
+    db: &C,
+    parent: &txns::Model,
+    refund_txn_id: &str,
+) -> Result<bool, DbErr> {
+    let refunded = refunded_total(db, &parent.txn_id, &[TxnStatus::Succeeded]).await?;
+    if refunded < parent.amount {
+        return Ok(false);
+    }
+
+    txn_status::advance(
+        db,
+        Transition {
+            txn_id: &parent.txn_id,
+            from: Some(TxnStatus::Succeeded),
+            to: TxnStatus::Reversed,
+            reason_code: None,
+            message: Some(format!("Fully refunded by {}", refund_txn_id)),
+            actor: txn_status::ACTOR_PROCESSOR,
+        },
+    )
+    .await
+}
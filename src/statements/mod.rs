@@ -0,0 +1,89 @@
+use rust_decimal::Decimal;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::*;
+
+/// Signed effect of a txn on its own account's balance. Only settled txns
+/// count; a reversed purchase still counts because its refunds are listed
+/// as txns of their own.
+const SIGNED_AMOUNT: &str = "CASE WHEN txn_type IN ('credit', 'refund', 'transfer_in', 'exchange_in') \
+     THEN amount ELSE -amount END";
+const SETTLED: &str = "status IN ('succeeded', 'reversed')";
+
+#[derive(Debug, FromQueryResult)]
+pub struct Line {
+    pub txn_id: String,
+    pub txn_type: String,
+    pub status: String,
+    pub counterparty_account_id: Option<String>,
+    pub amount: Decimal,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct Opening {
+    balance: Decimal,
+}
+
+/// Balance of `account_id` just before `from`: its current balance minus
+/// every settled txn created since. With no `from` this is the balance the
+/// account was funded with before its first txn.
+pub async fn opening_balance<C: ConnectionTrait>(
+    db: &C,
+    account_id: &str,
+    from: Option<DateTimeWithTimeZone>,
+) -> Result<Decimal, DbErr> {
+    let opening = Opening::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!(
+            "SELECT a.balance - COALESCE(( \
+                 SELECT SUM({SIGNED_AMOUNT}) FROM txns \
+                 WHERE account_id = $1 AND {SETTLED} \
+                   AND ($2::timestamptz IS NULL OR created_at >= $2) \
+             ), 0) AS balance \
+             FROM account a WHERE a.account_id = $1"
+        ),
+        [account_id.into(), from.into()],
+    ))
+    .one(db)
+    .await?;
+
+    Ok(opening.map(|o| o.balance).unwrap_or(Decimal::ZERO))
+}
+
+/// Up to `limit` settled txns of `account_id` created in `[from, to)`,
+/// oldest first, with `amount` signed by its effect on the balance. `after`
+/// is the last line of the previous page; each page is its own short query,
+/// so no connection is held while the client reads.
+pub async fn lines<C: ConnectionTrait>(
+    db: &C,
+    account_id: &str,
+    from: Option<DateTimeWithTimeZone>,
+    to: DateTimeWithTimeZone,
+    after: Option<&Line>,
+    limit: u64,
+) -> Result<Vec<Line>, DbErr> {
+    Line::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!(
+            "SELECT txn_id, txn_type, status, counterparty_account_id, created_at, \
+                    {SIGNED_AMOUNT} AS amount \
+             FROM txns \
+             WHERE account_id = $1 AND {SETTLED} \
+               AND ($2::timestamptz IS NULL OR created_at >= $2) \
+               AND created_at < $3 \
+               AND ($4::timestamptz IS NULL OR (created_at, txn_id) > ($4::timestamptz, $5::text)) \
+             ORDER BY created_at, txn_id \
+             LIMIT $6"
+        ),
+        [
+            account_id.into(),
+            from.into(),
+            to.into(),
+            after.map(|line| line.created_at).into(),
+            after.map(|line| line.txn_id.clone()).into(),
+            (limit as i64).into(),
+        ],
+    ))
+    .all(db)
+    .await
+}
@@ -0,0 +1,176 @@
+use std::fmt;
+
+use chrono::{FixedOffset, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+
+pub use crate::entities::sea_orm_active_enums::TxnStatus;
+use crate::entities::{prelude::*, txn_status_history, txns};
+
+pub const ACTOR_PROCESSOR: &str = "processor";
+
+pub fn user_actor(user_id: &str) -> String {
+    format!("user:{}", user_id)
+}
+
+impl TxnStatus {
+    pub fn can_transition_to(self, to: TxnStatus) -> bool {
+        use TxnStatus::*;
+
+        matches!(
+            (self, to),
+            (Pending, Processing)
+                | (Pending, Failed)
+                | (Pending, Cancelled)
+                | (Pending, Expired)
+                | (Processing, Succeeded)
+                | (Processing, Failed)
+                | (Succeeded, Reversed)
+        )
+    }
+}
+
+impl fmt::Display for TxnStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_value())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IllegalTransition {
+    pub from: TxnStatus,
+    pub to: TxnStatus,
+}
+
+impl fmt::Display for IllegalTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "illegal transaction status transition {} -> {}", self.from, self.to)
+    }
+}
+
+pub fn transition(from: TxnStatus, to: TxnStatus) -> Result<TxnStatus, IllegalTransition> {
+    if from.can_transition_to(to) {
+        Ok(to)
+    } else {
+        Err(IllegalTransition { from, to })
+    }
+}
+
+/// One status change to append to a transaction's timeline.
+pub struct Transition<'a> {
+    pub txn_id: &'a str,
+    pub from: Option<TxnStatus>,
+    pub to: TxnStatus,
+    pub reason_code: Option<&'a str>,
+    pub message: Option<String>,
+    pub actor: &'a str,
+}
+
+/// Appends `transition` to `txn_status_history`. Run it on the same
+/// connection/transaction that updates `txns.status`.
+pub async fn record<C: ConnectionTrait>(db: &C, transition: Transition<'_>) -> Result<(), DbErr> {
+    TxnStatusHistory::insert(txn_status_history::ActiveModel {
+        txn_id: Set(transition.txn_id.to_string()),
+        from_status: Set(transition.from),
+        to_status: Set(transition.to),
+        reason_code: Set(transition.reason_code.map(str::to_string)),
+        message: Set(transition.message),
+        actor: Set(transition.actor.to_string()),
+        created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap())),
+        ..Default::default()
+    })
+    .exec_without_returning(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Compare-and-set: moves the transaction from `transition.from` to
+/// `transition.to` only if it is still in `transition.from`, and records the
+/// change in its timeline. Returns `Ok(false)` when another writer got there
+/// first (e.g. a redelivered event for an already-settled transaction).
+pub async fn advance<C: ConnectionTrait>(db: &C, transition: Transition<'_>) -> Result<bool, DbErr> {
+    let from = transition
+        .from
+        .ok_or_else(|| DbErr::Custom("a status change needs a from status".to_string()))?;
+    self::transition(from, transition.to).map_err(|e| DbErr::Custom(e.to_string()))?;
+
+    let mut update = Txns::update_many()
+        .col_expr(txns::Column::Status, Expr::value(transition.to))
+        .filter(txns::Column::TxnId.eq(transition.txn_id))
+        .filter(txns::Column::Status.eq(from));
+    if let Some(reason_code) = transition.reason_code {
+        update = update.col_expr(txns::Column::FailureReason, Expr::value(reason_code));
+    }
+
+    if update.exec(db).await?.rows_affected == 0 {
+        return Ok(false);
+    }
+
+    record(db, transition).await?;
+    Ok(true)
+}
+
+/// Full timeline for a transaction, oldest first.
+pub async fn timeline<C: ConnectionTrait>(
+    db: &C,
+    txn_id: &str,
+) -> Result<Vec<txn_status_history::Model>, DbErr> {
+    TxnStatusHistory::find()
+        .filter(txn_status_history::Column::TxnId.eq(txn_id))
+        .order_by_asc(txn_status_history::Column::CreatedAt)
+        .all(db)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TxnStatus::*;
+
+    const LEGAL: [(TxnStatus, TxnStatus); 7] = [
+        (Pending, Processing),
+        (Pending, Failed),
+        (Pending, Cancelled),
+        (Pending, Expired),
+        (Processing, Succeeded),
+        (Processing, Failed),
+        (Succeeded, Reversed),
+    ];
+
+    #[test]
+    fn only_listed_transitions_are_legal() {
+        for from in TxnStatus::iter() {
+            for to in TxnStatus::iter() {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    LEGAL.contains(&(from, to)),
+                    "{} -> {}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn illegal_transitions_are_rejected() {
+        let cases = [
+            (Succeeded, Pending),
+            (Reversed, Succeeded),
+            (Failed, Succeeded),
+            (Pending, Succeeded),
+            (Processing, Pending),
+            (Succeeded, Succeeded),
+            (Cancelled, Processing),
+            (Expired, Pending),
+        ];
+        for (from, to) in cases {
+            assert_eq!(transition(from, to), Err(IllegalTransition { from, to }));
+        }
+
+        for (from, to) in LEGAL {
+            assert_eq!(transition(from, to), Ok(to));
+        }
+    }
+}
@@ -1,2 +1,3 @@
+pub mod money;
 pub mod random;
 pub mod validations;
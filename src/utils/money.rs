@@ -0,0 +1,25 @@
+use rust_decimal::Decimal;
+
+/// Number of minor-unit digits for an ISO 4217 currency code.
+///
+/// Amounts are stored as `NUMERIC(20, 4)`, which is wide enough for every
+/// currency we support; this table decides how many of those digits a given
+/// currency is actually allowed to use.
+pub fn minor_units(currency_code: &str) -> Option<u32> {
+    match currency_code {
+        "JPY" | "KRW" | "VND" | "CLP" | "ISK" | "UGX" => Some(0),
+        "INR" | "USD" | "EUR" | "GBP" | "AUD" | "CAD" | "CHF" | "CNY" | "SGD" | "AED" | "HKD"
+        | "NZD" | "SEK" | "NOK" | "DKK" | "ZAR" | "BRL" | "MXN" => Some(2),
+        "BHD" | "KWD" | "OMR" | "JOD" | "TND" => Some(3),
+        _ => None,
+    }
+}
+
+/// Returns true when `amount` does not carry more decimal places than the
+/// currency's minor unit allows (e.g. `10.005` is rejected for INR).
+pub fn fits_currency_scale(amount: &Decimal, currency_code: &str) -> bool {
+    match minor_units(currency_code) {
+        Some(scale) => amount.normalize().scale() <= scale,
+        None => false,
+    }
+}
@@ -1,6 +1,13 @@
 use rand::{Rng, rng};
+use rust_decimal::Decimal;
 
-pub fn generate_initial_balance() -> f32 {
+use super::money::minor_units;
+
+/// Random sign-up balance between 2,000 and 5,000 units, drawn in minor
+/// units so it is exact to `currency_code`'s scale.
+pub fn generate_initial_balance(currency_code: &str) -> Decimal {
     let mut rng = rng();
-    rng.random_range(2000.0..=5000.0)
+    let scale = minor_units(currency_code).unwrap_or(2);
+    let unit = 10_i64.pow(scale);
+    Decimal::new(rng.random_range(2_000 * unit..=5_000 * unit), scale)
 }
@@ -1,6 +1,9 @@
 use garde::Report;
+use rust_decimal::Decimal;
 use serde_json::{Value, json};
 
+use super::money::minor_units;
+
 pub fn format_validation_errors_json(report: Report) -> Value {
     let formatted = format!("{}", report);
     let errors = formatted
@@ -32,7 +35,7 @@ pub fn validate_optional_name(
             .chars()
             .all(|c| c.is_alphabetic() || c == '-' || c == '\'' || c == ' ');
 
-        if len < 2 || len > 50 {
+        if !(2..=50).contains(&len) {
             return Err(garde::Error::new("name must be 2–50 characters long"));
         }
 
@@ -43,18 +46,44 @@ pub fn validate_optional_name(
     Ok(())
 }
 
+pub fn is_valid_currency_code(value: &str, _context: &()) -> garde::Result {
+    if minor_units(value).is_none() {
+        return Err(garde::Error::new(
+            "currency_code must be a supported ISO 4217 code",
+        ));
+    }
+    Ok(())
+}
+
 #[derive(Default)]
 pub struct TxnTypeContext;
 
 pub fn is_valid_txn_type(value: &str, _context: &TxnTypeContext) -> garde::Result {
-    if value != "purchase" && value != "credit" {
+    if !matches!(value, "purchase" | "credit" | "transfer" | "exchange") {
         return Err(garde::Error::new(
-            "txn_type must be either 'purchase' or 'credit'",
+            "txn_type must be one of 'purchase', 'credit', 'transfer' or 'exchange'",
         ));
     }
     Ok(())
 }
 
+pub fn is_valid_amount(value: &Decimal, _context: &TxnTypeContext) -> garde::Result {
+    if value.is_sign_negative() || value.is_zero() {
+        return Err(garde::Error::new("amount must be greater than zero"));
+    }
+    if value.normalize().scale() > 4 {
+        return Err(garde::Error::new("amount must have at most 4 decimal places"));
+    }
+    Ok(())
+}
+
+pub fn is_valid_refund_amount(value: &Option<Decimal>, context: &TxnTypeContext) -> garde::Result {
+    match value {
+        Some(amount) => is_valid_amount(amount, context),
+        None => Ok(()),
+    }
+}
+
 #[derive(Default)]
 pub struct TxnViewContext;
 
@@ -0,0 +1,230 @@
+mod common;
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use common::{balance, register_and_login, wait_for_settlement};
+use payment_service::AppConfig;
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::{Value, json};
+use rust_decimal::Decimal;
+
+#[rocket::async_test]
+async fn open_list_and_choose_accounts_by_currency() {
+    let client = common::client().await;
+    let token = register_and_login(&client, "accounts").await;
+    let auth = || Header::new("token", token.clone());
+
+    let response = client
+        .post("/accounts")
+        .header(auth())
+        .json(&json!({ "currency_code": "USD" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+    let opened: Value = response.into_json().await.unwrap();
+    let usd_id = opened["account"]["account_id"].as_str().unwrap().to_string();
+    assert_eq!(opened["account"]["balance"], "0");
+
+    let response = client
+        .post("/accounts")
+        .header(auth())
+        .json(&json!({ "currency_code": "USD" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Conflict);
+
+    let response = client
+        .post("/accounts")
+        .header(auth())
+        .json(&json!({ "currency_code": "XYZ" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response = client.get("/accounts").header(auth()).dispatch().await;
+    let listed: Value = response.into_json().await.unwrap();
+    let currencies: Vec<_> = listed["accounts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|acc| acc["currency_code"].as_str().unwrap())
+        .collect();
+    assert_eq!(currencies, ["INR", "USD"]);
+
+    let response = client.get("/accounts/balance").header(auth()).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response = client
+        .get(format!("/accounts/balance?account_id={}", usd_id))
+        .header(auth())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let balance: Value = response.into_json().await.unwrap();
+    assert_eq!(balance["currency_code"], "USD");
+
+    let response = client
+        .post("/transactions/create")
+        .header(auth())
+        .json(&json!({ "amount": "10.00", "txn_type": "credit" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response = client
+        .post("/transactions/create")
+        .header(auth())
+        .json(&json!({ "amount": "10.00", "txn_type": "credit", "account_id": usd_id }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Accepted);
+    let created: Value = response.into_json().await.unwrap();
+    assert_eq!(created["transaction"]["currency_code"], "USD");
+}
+
+#[rocket::async_test]
+async fn cannot_use_another_users_account() {
+    let client = common::client().await;
+    let owner = register_and_login(&client, "accounts-owner").await;
+    let other = register_and_login(&client, "accounts-other").await;
+
+    let response = client
+        .post("/accounts")
+        .header(Header::new("token", owner.clone()))
+        .json(&json!({ "currency_code": "EUR" }))
+        .dispatch()
+        .await;
+    let opened: Value = response.into_json().await.unwrap();
+    let eur_id = opened["account"]["account_id"].as_str().unwrap();
+
+    let response = client
+        .post("/transactions/create")
+        .header(Header::new("token", other))
+        .json(&json!({ "amount": "1.00", "txn_type": "purchase", "account_id": eur_id }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+async fn settle(client: &Client, token: &str, amount: &str, txn_type: &str) {
+    let response = client
+        .post("/transactions/create")
+        .header(Header::new("token", token.to_string()))
+        .json(&json!({ "amount": amount, "txn_type": txn_type }))
+        .dispatch()
+        .await;
+    let created: Value = response.into_json().await.unwrap();
+    let txn_id = created["transaction"]["txn_id"].as_str().unwrap();
+
+    let settled = wait_for_settlement(client, token, txn_id).await;
+    assert_eq!(settled["transaction"]["status"], "succeeded");
+}
+
+#[rocket::async_test]
+async fn statement_runs_from_opening_to_closing_balance() {
+    let client = common::client().await;
+    let token = register_and_login(&client, "statement").await;
+    let auth = || Header::new("token", token.clone());
+
+    settle(&client, &token, "100.00", "credit").await;
+    settle(&client, &token, "40.25", "purchase").await;
+
+    let current = balance(&client, &token).await;
+
+    let response = client.get("/accounts").header(auth()).dispatch().await;
+    let listed: Value = response.into_json().await.unwrap();
+    let account_id = listed["accounts"][0]["account_id"].as_str().unwrap().to_string();
+
+    let response = client
+        .get(format!("/accounts/{}/statement?format=jsonl", account_id))
+        .header(auth())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = response.into_string().await.unwrap();
+    let rows: Vec<Value> = body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    let amount = |row: &Value, field: &str| Decimal::from_str(row[field].as_str().unwrap()).unwrap();
+
+    assert_eq!(rows.len(), 4);
+    assert_eq!(rows[0]["record"], "opening");
+    assert_eq!(rows[1]["txn_type"], "credit");
+    assert_eq!(amount(&rows[1], "balance"), amount(&rows[0], "balance") + amount(&rows[1], "amount"));
+    assert_eq!(amount(&rows[2], "amount"), Decimal::from_str("-40.25").unwrap());
+    assert_eq!(rows[3]["record"], "closing");
+    assert_eq!(amount(&rows[3], "balance"), current);
+
+    let response = client
+        .get(format!("/accounts/{}/statement?from=2999-01-01T00:00:00Z", account_id))
+        .header(auth())
+        .dispatch()
+        .await;
+    assert_eq!(response.content_type(), Some(ContentType::CSV));
+    let csv = response.into_string().await.unwrap();
+    let csv: Vec<&str> = csv.lines().collect();
+    assert_eq!(csv.len(), 3);
+    assert!(csv[0].starts_with("record,as_of,txn_id"));
+    assert!(csv[1].starts_with("opening,"));
+    assert!(csv[2].starts_with("closing,"));
+
+    let response = client
+        .get(format!("/accounts/{}/statement?format=pdf", account_id))
+        .header(auth())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let other = register_and_login(&client, "statement-other").await;
+    let response = client
+        .get(format!("/accounts/{}/statement", account_id))
+        .header(Header::new("token", other))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[rocket::async_test]
+async fn statement_pages_through_every_txn_once() {
+    let client = common::client_with(AppConfig {
+        statement_page_size: 2,
+        ..common::test_config()
+    })
+    .await;
+    let token = register_and_login(&client, "statement-pages").await;
+    let auth = || Header::new("token", token.clone());
+
+    let txns = [
+        ("10.00", "credit"),
+        ("1.00", "purchase"),
+        ("2.00", "purchase"),
+        ("3.00", "credit"),
+        ("4.00", "purchase"),
+    ];
+    for (amount, txn_type) in txns {
+        settle(&client, &token, amount, txn_type).await;
+    }
+
+    let response = client.get("/accounts").header(auth()).dispatch().await;
+    let listed: Value = response.into_json().await.unwrap();
+    let account_id = listed["accounts"][0]["account_id"].as_str().unwrap().to_string();
+
+    let response = client
+        .get(format!("/accounts/{}/statement?format=jsonl", account_id))
+        .header(auth())
+        .dispatch()
+        .await;
+    let body = response.into_string().await.unwrap();
+    let rows: Vec<Value> = body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    let amount = |row: &Value, field: &str| Decimal::from_str(row[field].as_str().unwrap()).unwrap();
+
+    // Five txns over three pages, each listed once, with an unbroken running balance.
+    assert_eq!(rows.len(), 7);
+    let txn_ids: HashSet<&str> = rows[1..6].iter().map(|row| row["txn_id"].as_str().unwrap()).collect();
+    assert_eq!(txn_ids.len(), 5);
+    for pair in rows.windows(2).take(5) {
+        assert_eq!(amount(&pair[1], "balance"), amount(&pair[0], "balance") + amount(&pair[1], "amount"));
+    }
+    assert_eq!(amount(&rows[6], "balance"), amount(&rows[5], "balance"));
+    assert_eq!(amount(&rows[6], "balance"), amount(&rows[0], "balance") + Decimal::from_str("6.00").unwrap());
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use common::{login, post_json, register, unique_email};
+use payment_service::entities::{admin_audit_log, prelude::*, users};
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::{Value, json};
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+
+/// Registers a user, gives them `role` directly in the database (there is
+/// no API for granting roles), and logs them in.
+async fn staff(client: &Client, prefix: &str, role: &str) -> String {
+    let email = unique_email(prefix);
+    register(client, &email).await;
+
+    let db = client.rocket().state::<DatabaseConnection>().unwrap();
+    Users::update_many()
+        .col_expr(users::Column::Role, Expr::value(role))
+        .filter(users::Column::Email.eq(email.clone()))
+        .exec(db)
+        .await
+        .unwrap();
+
+    login(client, &email).await
+}
+
+#[rocket::async_test]
+async fn only_staff_reach_the_admin_routes() {
+    let client = common::client().await;
+    let email = unique_email("admin-target");
+    register(&client, &email).await;
+    let customer = login(&client, &email).await;
+    let support = staff(&client, "admin-support", "support").await;
+
+    let search = format!("/admin/users?email={}", email);
+    let response = client
+        .get(search.clone())
+        .header(Header::new("token", customer.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response = client
+        .get(search)
+        .header(Header::new("token", support.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let found: Value = response.into_json().await.unwrap();
+    assert_eq!(found["users"][0]["email"], email.as_str());
+    assert_eq!(found["users"][0]["role"], "user");
+    let account_id = found["users"][0]["accounts"][0]["account_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // Wildcards are matched literally rather than listing every user.
+    for wildcard in ["%25", "_@example.com"] {
+        let response = client
+            .get(format!("/admin/users?email={}", wildcard))
+            .header(Header::new("token", support.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let found: Value = response.into_json().await.unwrap();
+        assert_eq!(found["users"], json!([]), "{}", wildcard);
+    }
+
+    let response = client
+        .get(format!("/admin/accounts/{}", account_id))
+        .header(Header::new("token", support.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let (status, _) = post_json(&client, &support, format!("/admin/accounts/{}/freeze", account_id), json!({})).await;
+    assert_eq!(status, Status::Forbidden);
+}
+
+#[rocket::async_test]
+async fn frozen_accounts_cannot_transact_until_unfrozen() {
+    let client = common::client().await;
+    let email = unique_email("admin-frozen");
+    register(&client, &email).await;
+    let customer = login(&client, &email).await;
+    let admin = staff(&client, "admin-admin", "admin").await;
+
+    let response = client
+        .get("/accounts")
+        .header(Header::new("token", customer.clone()))
+        .dispatch()
+        .await;
+    let listed: Value = response.into_json().await.unwrap();
+    let account_id = listed["accounts"][0]["account_id"].as_str().unwrap().to_string();
+
+    // A hold placed before the freeze cannot be captured during it.
+    let authorize = || "/transactions/authorize".to_string();
+    let (status, held) = post_json(&client, &customer, authorize(), json!({ "amount": "2.00" })).await;
+    assert_eq!(status, Status::Created);
+    let capture = format!("/transactions/holds/{}/capture", held["hold"]["hold_id"].as_str().unwrap());
+
+    let freeze = format!("/admin/accounts/{}/freeze", account_id);
+    let (status, body) = post_json(&client, &admin, freeze.clone(), json!({})).await;
+    assert_eq!(status, Status::Ok);
+    assert_eq!(body["account"]["status"], "frozen");
+
+    let (status, _) = post_json(&client, &admin, freeze, json!({})).await;
+    assert_eq!(status, Status::Conflict);
+
+    let (status, declined) = post_json(&client, &customer, authorize(), json!({ "amount": "1.00" })).await;
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(declined["reason"], "account_inactive");
+
+    let (status, declined) = post_json(&client, &customer, capture.clone(), json!({})).await;
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(declined["reason"], "account_inactive");
+
+    let (status, body) = post_json(&client, &admin, format!("/admin/accounts/{}/unfreeze", account_id), json!({})).await;
+    assert_eq!(status, Status::Ok);
+    assert_eq!(body["account"]["status"], "active");
+
+    let (status, _) = post_json(&client, &customer, authorize(), json!({ "amount": "1.00" })).await;
+    assert_eq!(status, Status::Created);
+
+    let (status, _) = post_json(&client, &customer, capture, json!({})).await;
+    assert_eq!(status, Status::Ok);
+
+    // Each change is audited; the rejected second freeze is not.
+    let db = client.rocket().state::<DatabaseConnection>().unwrap();
+    let audited: Vec<String> = AdminAuditLog::find()
+        .filter(admin_audit_log::Column::Target.eq(account_id.clone()))
+        .order_by_asc(admin_audit_log::Column::CreatedAt)
+        .all(db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|row| row.action)
+        .collect();
+    assert_eq!(audited, ["freeze_account", "unfreeze_account"]);
+}
@@ -0,0 +1,360 @@
+mod common;
+
+use common::{PASSWORD, mailed_token, me, register, register_unverified, unique_email};
+use payment_service::AppConfig;
+use payment_service::entities::{login_lockouts, prelude::*};
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::{Value, json};
+use sea_orm::*;
+
+struct Session {
+    token: String,
+    refresh_token: String,
+}
+
+async fn login(client: &Client, email: &str, device: &str) -> Session {
+    let response = client
+        .post("/auth/login")
+        .json(&json!({ "email": email, "password": PASSWORD, "device": device }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let body: Value = response.into_json().await.unwrap();
+    Session {
+        token: body["token"].as_str().unwrap().to_string(),
+        refresh_token: body["refresh_token"].as_str().unwrap().to_string(),
+    }
+}
+
+async fn refresh(client: &Client, refresh_token: &str) -> Result<Session, Status> {
+    let response = client
+        .post("/auth/refresh")
+        .json(&json!({ "refresh_token": refresh_token }))
+        .dispatch()
+        .await;
+    if response.status() != Status::Ok {
+        return Err(response.status());
+    }
+    let body: Value = response.into_json().await.unwrap();
+    Ok(Session {
+        token: body["token"].as_str().unwrap().to_string(),
+        refresh_token: body["refresh_token"].as_str().unwrap().to_string(),
+    })
+}
+
+#[rocket::async_test]
+async fn refresh_rotates_and_reuse_revokes_the_family() {
+    let client = common::client().await;
+    let email = unique_email("refresh");
+    register(&client, &email).await;
+    let first = login(&client, &email, "laptop").await;
+
+    let second = refresh(&client, &first.refresh_token).await.unwrap();
+    assert_ne!(second.refresh_token, first.refresh_token);
+    assert_eq!(me(&client, &second.token).await, Status::Ok);
+
+    assert_eq!(
+        refresh(&client, &first.refresh_token).await.err(),
+        Some(Status::Unauthorized)
+    );
+    assert_eq!(
+        refresh(&client, &second.refresh_token).await.err(),
+        Some(Status::Unauthorized)
+    );
+}
+
+#[rocket::async_test]
+async fn logout_ends_one_session_and_logout_all_ends_every_session() {
+    let client = common::client().await;
+    let email = unique_email("logout");
+    register(&client, &email).await;
+    let phone = login(&client, &email, "phone").await;
+    let laptop = login(&client, &email, "laptop").await;
+
+    // Signing in on the laptop did not sign the phone out.
+    assert_eq!(me(&client, &phone.token).await, Status::Ok);
+
+    let response = client
+        .post("/auth/logout")
+        .header(Header::new("token", phone.token.clone()))
+        .json(&json!({ "refresh_token": phone.refresh_token }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        refresh(&client, &phone.refresh_token).await.err(),
+        Some(Status::Unauthorized)
+    );
+
+    let laptop = refresh(&client, &laptop.refresh_token).await.unwrap();
+
+    let response = client
+        .post("/auth/logout-all")
+        .header(Header::new("token", laptop.token.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(me(&client, &laptop.token).await, Status::Unauthorized);
+    assert_eq!(
+        refresh(&client, &laptop.refresh_token).await.err(),
+        Some(Status::Unauthorized)
+    );
+}
+
+async fn attempt(client: &Client, email: &str, password: &str, ip: &str) -> (Status, Option<String>) {
+    let response = client
+        .post("/auth/login")
+        .remote(format!("{}:4000", ip).parse().unwrap())
+        .json(&json!({ "email": email, "password": password }))
+        .dispatch()
+        .await;
+    let retry_after = response.headers().get_one("Retry-After").map(str::to_string);
+    (response.status(), retry_after)
+}
+
+#[rocket::async_test]
+async fn failures_back_off_exponentially() {
+    let client = common::client().await;
+    let email = unique_email("backoff");
+    register(&client, &email).await;
+
+    let (status, _) = attempt(&client, &email, "wrong-password-1", "10.0.0.1").await;
+    assert_eq!(status, Status::Unauthorized);
+
+    // Even the right password is refused until the backoff delay passes.
+    let (status, retry_after) = attempt(&client, &email, PASSWORD, "10.0.0.1").await;
+    assert_eq!(status, Status::TooManyRequests);
+    assert_eq!(retry_after.as_deref(), Some("1"));
+
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let (status, _) = attempt(&client, &email, PASSWORD, "10.0.0.1").await;
+    assert_eq!(status, Status::Ok);
+}
+
+#[rocket::async_test]
+async fn repeated_failures_lock_the_account_and_are_audited() {
+    let client = common::client_with(AppConfig {
+        login_max_failures: 3,
+        login_backoff_base_secs: 0,
+        login_lockout_secs: 60,
+        ..common::test_config()
+    })
+    .await;
+    let email = unique_email("lockout");
+    register(&client, &email).await;
+
+    for _ in 0..3 {
+        let (status, _) = attempt(&client, &email, "wrong-password-1", "10.0.0.2").await;
+        assert_eq!(status, Status::Unauthorized);
+    }
+
+    let (status, retry_after) = attempt(&client, &email, PASSWORD, "10.0.0.3").await;
+    assert_eq!(status, Status::TooManyRequests);
+    let retry_after: u64 = retry_after.unwrap().parse().unwrap();
+    assert!(retry_after > 0 && retry_after <= 60);
+
+    let db = client.rocket().state::<DatabaseConnection>().unwrap();
+    let audit = LoginLockouts::find()
+        .filter(login_lockouts::Column::Subject.eq(format!("email:{}", email)))
+        .all(db)
+        .await
+        .unwrap();
+    assert_eq!(audit.len(), 1);
+    assert_eq!(audit[0].failures, 3);
+    assert_eq!(audit[0].ip_address.as_deref(), Some("10.0.0.2"));
+}
+
+#[rocket::async_test]
+async fn one_ip_guessing_many_emails_is_locked_out() {
+    let client = common::client_with(AppConfig {
+        login_ip_max_failures: 2,
+        login_backoff_base_secs: 0,
+        ..common::test_config()
+    })
+    .await;
+    let email = unique_email("ip-lockout");
+    register(&client, &email).await;
+
+    for prefix in ["ip-guess-a", "ip-guess-b"] {
+        let (status, _) = attempt(&client, &unique_email(prefix), "wrong-password-1", "10.0.0.4").await;
+        assert_eq!(status, Status::Unauthorized);
+    }
+
+    let (status, _) = attempt(&client, &email, PASSWORD, "10.0.0.4").await;
+    assert_eq!(status, Status::TooManyRequests);
+    let (status, _) = attempt(&client, &email, PASSWORD, "10.0.0.5").await;
+    assert_eq!(status, Status::Ok);
+}
+
+#[rocket::async_test]
+async fn login_needs_a_verified_email() {
+    let client = common::client().await;
+    let email = unique_email("unverified");
+    register_unverified(&client, &email).await;
+
+    let (status, _) = attempt(&client, &email, PASSWORD, "10.0.0.6").await;
+    assert_eq!(status, Status::Forbidden);
+
+    let response = client
+        .post("/auth/verify-email")
+        .json(&json!({ "token": "not-a-token" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+
+    let response = client
+        .post("/auth/resend-verification")
+        .json(&json!({ "email": email }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Accepted);
+    let response = client
+        .post("/auth/verify-email")
+        .json(&json!({ "token": mailed_token(&email) }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let (status, _) = attempt(&client, &email, PASSWORD, "10.0.0.6").await;
+    assert_eq!(status, Status::Ok);
+}
+
+#[rocket::async_test]
+async fn password_reset_ends_sessions_and_works_once() {
+    let client = common::client().await;
+    let email = unique_email("reset");
+    register(&client, &email).await;
+    let session = login(&client, &email, "laptop").await;
+
+    // Unknown addresses get the same answer.
+    for address in [unique_email("nobody"), email.clone()] {
+        let response = client
+            .post("/auth/forgot-password")
+            .json(&json!({ "email": address }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Accepted);
+    }
+    let token = mailed_token(&email);
+
+    let reset = |password: &'static str| {
+        client
+            .post("/auth/reset-password")
+            .json(&json!({ "token": token, "password": password }))
+            .dispatch()
+    };
+    assert_eq!(reset("short").await.status(), Status::UnprocessableEntity);
+    assert_eq!(reset("a-brand-new-password").await.status(), Status::Ok);
+    assert_eq!(reset("another-new-password").await.status(), Status::BadRequest);
+
+    assert_eq!(me(&client, &session.token).await, Status::Unauthorized);
+    assert_eq!(
+        refresh(&client, &session.refresh_token).await.err(),
+        Some(Status::Unauthorized)
+    );
+
+    let (status, _) = attempt(&client, &email, "a-brand-new-password", "10.0.0.7").await;
+    assert_eq!(status, Status::Ok);
+    let (status, _) = attempt(&client, &email, PASSWORD, "10.0.0.7").await;
+    assert_eq!(status, Status::Unauthorized);
+}
+
+async fn sign_up(client: &Client, email: &str, password: &str) -> (Status, Value) {
+    let response = client
+        .post("/auth/register")
+        .json(&json!({ "email": email, "password": password, "profile": {} }))
+        .dispatch()
+        .await;
+    let status = response.status();
+    (status, response.into_json().await.unwrap_or_default())
+}
+
+#[rocket::async_test]
+async fn register_enforces_the_password_policy() {
+    let blocklist = std::env::temp_dir().join("payments-test-blocklist.txt");
+    std::fs::write(&blocklist, "# common passwords\nPassword1234!\n").unwrap();
+    let client = common::client_with(AppConfig {
+        password_min_classes: 3,
+        password_blocklist_file: Some(blocklist.to_string_lossy().into_owned()),
+        ..common::test_config()
+    })
+    .await;
+    let email = unique_email("policy");
+
+    let rejected = [
+        "Sh0rt!",
+        "only-lowercase-and-symbols",
+        "password1234!",
+        &format!("{}-Pass1", email.split('@').next().unwrap()),
+        &format!("Aa1-{}", "x".repeat(80)),
+    ];
+    for password in rejected {
+        let (status, body) = sign_up(&client, &email, password).await;
+        assert_eq!(status, Status::UnprocessableEntity, "{}", password);
+        assert_eq!(body["errors"][0]["field"], "password");
+    }
+
+    let (status, _) = sign_up(&client, &email, "Correct-Horse-9").await;
+    assert_eq!(status, Status::Created);
+}
+
+#[rocket::async_test]
+async fn a_lower_minimum_length_lets_short_passwords_log_in() {
+    let client = common::client_with(AppConfig {
+        password_min_length: 8,
+        ..common::test_config()
+    })
+    .await;
+    let email = unique_email("short-password");
+
+    let (status, _) = sign_up(&client, &email, "Mule-42x").await;
+    assert_eq!(status, Status::Created);
+    let response = client
+        .post("/auth/verify-email")
+        .json(&json!({ "token": mailed_token(&email) }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let (status, _) = attempt(&client, &email, "Mule-42x", "10.0.9.1").await;
+    assert_eq!(status, Status::Ok);
+}
+
+#[rocket::async_test]
+async fn changing_the_password_ends_other_sessions() {
+    let client = common::client().await;
+    let email = unique_email("change");
+    register(&client, &email).await;
+    let phone = login(&client, &email, "phone").await;
+    let laptop = login(&client, &email, "laptop").await;
+
+    let change = |current: &'static str, new: &'static str| {
+        client
+            .put("/auth/password")
+            .header(Header::new("token", laptop.token.clone()))
+            .json(&json!({ "current_password": current, "new_password": new }))
+            .dispatch()
+    };
+    assert_eq!(change("wrong-password-1", "a-brand-new-password").await.status(), Status::Forbidden);
+
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    assert_eq!(change(PASSWORD, PASSWORD).await.status(), Status::UnprocessableEntity);
+    assert_eq!(change(PASSWORD, "short").await.status(), Status::UnprocessableEntity);
+
+    let response = change(PASSWORD, "a-brand-new-password").await;
+    assert_eq!(response.status(), Status::Ok);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(me(&client, body["token"].as_str().unwrap()).await, Status::Ok);
+
+    for session in [&phone, &laptop] {
+        assert_eq!(me(&client, &session.token).await, Status::Unauthorized);
+        assert_eq!(
+            refresh(&client, &session.refresh_token).await.err(),
+            Some(Status::Unauthorized)
+        );
+    }
+
+    let (status, _) = attempt(&client, &email, "a-brand-new-password", "10.0.0.9").await;
+    assert_eq!(status, Status::Ok);
+}
@@ -0,0 +1,266 @@
+#![allow(dead_code)]
+
+use chrono::{FixedOffset, Utc};
+use payment_service::entities::sea_orm_active_enums::TxnStatus;
+use payment_service::entities::{account, txns, users};
+use payment_service::ledger;
+use payment_service::{AppConfig, build};
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::{Value, json};
+use rocket::{Build, Rocket};
+use rust_decimal::Decimal;
+use sea_orm::*;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+pub const PASSWORD: &str = "correct-horse-battery";
+
+/// Config wired to the in-memory event bus so tests never need a Kafka
+/// broker, with mail written to files the tests can read and tokens signed
+/// by the fixture keys.
+pub fn test_config() -> AppConfig {
+    dotenvy::dotenv().ok();
+    AppConfig {
+        event_bus: "memory".to_string(),
+        mailer: "file".to_string(),
+        mail_dir: mail_dir().to_string_lossy().into_owned(),
+        jwt_key_dir: fixture("jwt_keys"),
+        jwt_signing_kid: None,
+        ..AppConfig::default()
+    }
+}
+
+/// Path of `name` under `tests/fixtures`.
+pub fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+pub fn mail_dir() -> PathBuf {
+    std::env::temp_dir().join("payments-test-mail")
+}
+
+/// The newest mail sent to `email`.
+pub fn last_mail(email: &str) -> String {
+    let suffix = format!("-{}.eml", email);
+    let mut names: Vec<_> = std::fs::read_dir(mail_dir())
+        .unwrap()
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.ends_with(&suffix))
+        .collect();
+    names.sort();
+    let name = names.last().unwrap_or_else(|| panic!("no mail for {}", email));
+    std::fs::read_to_string(mail_dir().join(name)).unwrap()
+}
+
+/// The token in the link of the newest mail sent to `email`.
+pub fn mailed_token(email: &str) -> String {
+    let mail = last_mail(email);
+    let start = mail.find("token=").expect("mail has a token link") + "token=".len();
+    mail[start..]
+        .split_whitespace()
+        .next()
+        .unwrap()
+        .to_string()
+}
+
+pub async fn test_rocket() -> Rocket<Build> {
+    build(test_config()).await
+}
+
+pub async fn client() -> Client {
+    client_with(test_config()).await
+}
+
+pub async fn client_with(config: AppConfig) -> Client {
+    Client::tracked(build(config).await)
+        .await
+        .expect("valid rocket instance")
+}
+
+pub fn unique_email(prefix: &str) -> String {
+    format!(
+        "{}-{}@example.com",
+        prefix,
+        chrono::Utc::now().timestamp_nanos_opt().unwrap()
+    )
+}
+
+/// Registers `email` and verifies it with the mailed link.
+pub async fn register(client: &Client, email: &str) {
+    register_unverified(client, email).await;
+    let response = client
+        .post("/auth/verify-email")
+        .json(&json!({ "token": mailed_token(email) }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+pub async fn register_unverified(client: &Client, email: &str) {
+    let response = client
+        .post("/auth/register")
+        .json(&json!({ "email": email, "password": PASSWORD, "profile": {} }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+}
+
+pub async fn login(client: &Client, email: &str) -> String {
+    let response = client
+        .post("/auth/login")
+        .json(&json!({ "email": email, "password": PASSWORD }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let body: Value = response.into_json().await.unwrap();
+    body["token"].as_str().unwrap().to_string()
+}
+
+pub async fn register_and_login(client: &Client, prefix: &str) -> String {
+    let email = unique_email(prefix);
+    register(client, &email).await;
+    login(client, &email).await
+}
+
+/// Status of `/auth/me` for `token`: `Ok` while it is a live access token.
+pub async fn me(client: &Client, token: &str) -> Status {
+    client
+        .get("/auth/me")
+        .header(Header::new("token", token.to_string()))
+        .dispatch()
+        .await
+        .status()
+}
+
+/// POSTs `body` as `token` and returns the status with the JSON response,
+/// or `Null` when the body is not JSON.
+pub async fn post_json(client: &Client, token: &str, uri: String, body: Value) -> (Status, Value) {
+    let response = client
+        .post(uri)
+        .header(Header::new("token", token.to_string()))
+        .json(&body)
+        .dispatch()
+        .await;
+    let status = response.status();
+    (status, response.into_json().await.unwrap_or(Value::Null))
+}
+
+/// Polls a transaction until the processor has moved it out of `pending`
+/// and `processing`, returning the last status response.
+pub async fn wait_for_settlement(client: &Client, token: &str, txn_id: &str) -> Value {
+    for _ in 0..50 {
+        let response = client
+            .get(format!("/transactions/status/{}", txn_id))
+            .header(Header::new("token", token.to_string()))
+            .dispatch()
+            .await;
+        let body: Value = response.into_json().await.unwrap();
+        let status = &body["transaction"]["status"];
+        if status != "pending" && status != "processing" {
+            return body;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    panic!("transaction {} was never processed", txn_id);
+}
+
+/// `balance` and `locked_balance` of the caller's account, or of
+/// `account_id` when they have several.
+pub async fn balances(client: &Client, token: &str, account_id: Option<&str>) -> (Decimal, Decimal) {
+    let uri = match account_id {
+        Some(account_id) => format!("/accounts/balance?account_id={}", account_id),
+        None => "/accounts/balance".to_string(),
+    };
+    let response = client
+        .get(uri)
+        .header(Header::new("token", token.to_string()))
+        .dispatch()
+        .await;
+    let body: Value = response.into_json().await.unwrap();
+    let field = |name: &str| Decimal::from_str(body[name].as_str().unwrap()).unwrap();
+    (field("balance"), field("locked_balance"))
+}
+
+/// Balance of the caller's only account.
+pub async fn balance(client: &Client, token: &str) -> Decimal {
+    balances(client, token, None).await.0
+}
+
+/// Database connection of a fresh, migrated test instance, for tests that
+/// set up rows directly instead of through the API.
+pub async fn test_db() -> DatabaseConnection {
+    test_rocket()
+        .await
+        .state::<DatabaseConnection>()
+        .expect("managed database connection")
+        .clone()
+}
+
+/// Inserts a user with one INR account funded with `balance`, posting the
+/// funding to the ledger so its invariants hold.
+pub async fn seed_account(db: &DatabaseConnection, prefix: &str, balance: Decimal) -> account::Model {
+    let now = Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap());
+    let user = users::ActiveModel {
+        email: Set(unique_email(prefix)),
+        password_hash: Set("not-a-real-hash".to_string()),
+        profile_data: Set(json!({})),
+        created_at: Set(now),
+        kyc_status: Set("pending".to_string()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap();
+
+    let acc = account::ActiveModel {
+        user_id: Set(user.user_id),
+        currency_code: Set("INR".to_string()),
+        balance: Set(balance),
+        locked_balance: Set(Decimal::ZERO),
+        updated_at: Set(now),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap();
+
+    ledger::post(
+        db,
+        None,
+        ledger::clearing_account("INR"),
+        acc.account_id.clone(),
+        balance,
+        "INR",
+    )
+    .await
+    .unwrap();
+
+    acc
+}
+
+/// Inserts a pending INR txn on `acc`, as `POST /transactions/create` would
+/// before the processor picks it up. `counterparty` is the receiving
+/// account of a transfer.
+pub async fn insert_pending_txn(
+    db: &DatabaseConnection,
+    acc: &account::Model,
+    txn_type: &str,
+    amount: Decimal,
+    counterparty: Option<&account::Model>,
+) -> txns::Model {
+    txns::ActiveModel {
+        account_id: Set(acc.account_id.clone()),
+        amount: Set(amount),
+        currency_code: Set("INR".to_string()),
+        txn_type: Set(txn_type.to_string()),
+        status: Set(TxnStatus::Pending),
+        created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap())),
+        counterparty_account_id: Set(counterparty.map(|to| to.account_id.clone())),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
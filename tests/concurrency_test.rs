@@ -0,0 +1,197 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{insert_pending_txn, seed_account, test_db};
+use payment_service::entities::sea_orm_active_enums::TxnStatus;
+use payment_service::entities::{account, prelude::*, txns};
+use payment_service::ledger;
+use payment_service::processing::rules::RulesProcessor;
+use payment_service::processing::{TransactionEvent, TransactionProcessor, handle_transaction};
+use payment_service::txn_status::{self, ACTOR_PROCESSOR, Transition};
+use rust_decimal::Decimal;
+use sea_orm::*;
+
+const PURCHASES: usize = 40;
+
+fn processor() -> Arc<dyn TransactionProcessor> {
+    Arc::new(RulesProcessor {
+        max_txn_amount: Decimal::new(100_000, 0),
+    })
+}
+
+fn event(txn: txns::Model) -> TransactionEvent {
+    TransactionEvent {
+        txn_id: txn.txn_id,
+        account_id: txn.account_id,
+        amount: txn.amount,
+        txn_type: txn.txn_type,
+    }
+}
+
+async fn insert_purchase(db: &DatabaseConnection, acc: &account::Model, amount: Decimal) -> TransactionEvent {
+    event(insert_pending_txn(db, acc, "purchase", amount, None).await)
+}
+
+async fn insert_transfer(
+    db: &DatabaseConnection,
+    from: &account::Model,
+    to: &account::Model,
+    amount: Decimal,
+) -> TransactionEvent {
+    event(insert_pending_txn(db, from, "transfer", amount, Some(to)).await)
+}
+
+async fn balance_of(db: &DatabaseConnection, acc: &account::Model) -> Decimal {
+    Account::find_by_id(acc.account_id.clone())
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap()
+        .balance
+}
+
+#[rocket::async_test]
+async fn concurrent_purchases_never_overdraw() {
+    let db = test_db().await;
+    let opening_balance = Decimal::new(10_000, 2);
+    let acc = seed_account(&db, "overdraw", opening_balance).await;
+    let processor = processor();
+
+    let amount = Decimal::new(1_000, 2);
+    let mut events = Vec::with_capacity(PURCHASES);
+    for _ in 0..PURCHASES {
+        events.push(insert_purchase(&db, &acc, amount).await);
+    }
+
+    let handles: Vec<_> = events
+        .into_iter()
+        .map(|event| tokio::spawn(handle_transaction(event, db.clone(), processor.clone())))
+        .collect();
+
+    for handle in handles {
+        handle.await.unwrap();
+    }
+
+    let succeeded = Txns::find()
+        .filter(txns::Column::AccountId.eq(acc.account_id.clone()))
+        .filter(txns::Column::Status.eq(TxnStatus::Succeeded))
+        .count(&db)
+        .await
+        .unwrap();
+    let final_balance = balance_of(&db, &acc).await;
+
+    assert!(final_balance >= Decimal::ZERO);
+    assert_eq!(succeeded, 10);
+    assert_eq!(final_balance, opening_balance - amount * Decimal::from(succeeded));
+
+    let mismatches = ledger::check_invariants(&db).await.unwrap();
+    assert!(mismatches.iter().all(|m| m.account_id != acc.account_id));
+}
+
+#[rocket::async_test]
+async fn redelivered_event_is_a_noop() {
+    let db = test_db().await;
+    let opening_balance = Decimal::new(10_000, 2);
+    let acc = seed_account(&db, "redelivery", opening_balance).await;
+    let processor = processor();
+
+    let amount = Decimal::new(2_500, 2);
+    let event = insert_purchase(&db, &acc, amount).await;
+    let txn_id = event.txn_id.clone();
+    let redelivered = TransactionEvent {
+        txn_id: event.txn_id.clone(),
+        account_id: event.account_id.clone(),
+        amount,
+        txn_type: event.txn_type.clone(),
+    };
+
+    handle_transaction(event, db.clone(), processor.clone()).await;
+    handle_transaction(redelivered, db.clone(), processor.clone()).await;
+
+    let txn = Txns::find_by_id(txn_id).one(&db).await.unwrap().unwrap();
+    assert_eq!(txn.status, TxnStatus::Succeeded);
+    assert_eq!(balance_of(&db, &acc).await, opening_balance - amount);
+}
+
+#[rocket::async_test]
+async fn opposite_transfers_settle_without_deadlock() {
+    let db = test_db().await;
+    let opening_balance = Decimal::new(10_000, 2);
+    let a = seed_account(&db, "transfer-a", opening_balance).await;
+    let b = seed_account(&db, "transfer-b", opening_balance).await;
+    let processor = processor();
+
+    let amount = Decimal::new(100, 2);
+    let mut events = Vec::with_capacity(PURCHASES);
+    for i in 0..PURCHASES {
+        let (from, to) = if i % 2 == 0 { (&a, &b) } else { (&b, &a) };
+        events.push(insert_transfer(&db, from, to, amount).await);
+    }
+
+    let handles: Vec<_> = events
+        .into_iter()
+        .map(|event| tokio::spawn(handle_transaction(event, db.clone(), processor.clone())))
+        .collect();
+
+    for handle in handles {
+        handle.await.unwrap();
+    }
+
+    let unsettled = Txns::find()
+        .filter(txns::Column::AccountId.is_in([a.account_id.clone(), b.account_id.clone()]))
+        .filter(txns::Column::Status.ne(TxnStatus::Succeeded))
+        .count(&db)
+        .await
+        .unwrap();
+    assert_eq!(unsettled, 0);
+
+    // Each account sent and received the same number of transfers.
+    assert_eq!(balance_of(&db, &a).await, opening_balance);
+    assert_eq!(balance_of(&db, &b).await, opening_balance);
+
+    let mismatches = ledger::check_invariants(&db).await.unwrap();
+    assert!(
+        mismatches
+            .iter()
+            .all(|m| m.account_id != a.account_id && m.account_id != b.account_id)
+    );
+}
+
+fn claim(txn_id: &str) -> Transition<'_> {
+    Transition {
+        txn_id,
+        from: Some(TxnStatus::Pending),
+        to: TxnStatus::Processing,
+        reason_code: None,
+        message: None,
+        actor: ACTOR_PROCESSOR,
+    }
+}
+
+#[rocket::async_test]
+async fn advance_lets_exactly_one_racer_win() {
+    let db = test_db().await;
+    let acc = seed_account(&db, "txn-status", Decimal::new(100, 0)).await;
+    let txn = insert_pending_txn(&db, &acc, "purchase", Decimal::new(10, 0), None).await;
+
+    let (a, b) = tokio::join!(
+        txn_status::advance(&db, claim(&txn.txn_id)),
+        txn_status::advance(&db, claim(&txn.txn_id))
+    );
+    assert_ne!(a.unwrap(), b.unwrap());
+
+    // The loser's stale `from` no longer matches, so nothing changes.
+    assert!(!txn_status::advance(&db, claim(&txn.txn_id)).await.unwrap());
+    // An illegal move is refused before the database is touched.
+    let illegal = Transition {
+        from: Some(TxnStatus::Processing),
+        to: TxnStatus::Pending,
+        ..claim(&txn.txn_id)
+    };
+    assert!(txn_status::advance(&db, illegal).await.is_err());
+
+    let stored = Txns::find_by_id(txn.txn_id.clone()).one(&db).await.unwrap().unwrap();
+    assert_eq!(stored.status, TxnStatus::Processing);
+    assert_eq!(txn_status::timeline(&db, &txn.txn_id).await.unwrap().len(), 1);
+}
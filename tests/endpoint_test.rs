@@ -1,15 +1,85 @@
-use payment_service::rocket;
-use rocket::local::asynchronous::Client;
-use rocket::http::Status;
+mod common;
+
+use common::register_and_login;
+use payment_service::entities::{outbox_events, prelude::{OutboxEvents, Txns}, txns};
+use rocket::http::{Header, Status};
+use rocket::serde::json::{Value, json};
 use rocket::uri;
+use sea_orm::*;
 
 #[rocket::async_test]
 async fn hello_rocket() {
-    let rocket_instance = rocket().await;
-    let client = Client::tracked(rocket_instance).await.expect("valid rocket instance");
+    let client = common::client().await;
 
     let response = client.get(uri!(payment_service::index)).dispatch().await;
 
     assert_eq!(response.status(), Status::Ok);
     assert_eq!(response.into_string().await.unwrap(), "Hello, Rocket!");
-}
\ No newline at end of file
+}
+
+#[rocket::async_test]
+async fn register_login_and_balance() {
+    let client = common::client().await;
+    let token = register_and_login(&client, "balance").await;
+
+    let response = client
+        .get("/accounts/balance")
+        .header(Header::new("token", token))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["currency_code"], "INR");
+    assert!(body["balance"].is_string());
+}
+
+#[rocket::async_test]
+async fn idempotent_transaction_create() {
+    let client = common::client().await;
+    let token = register_and_login(&client, "idempotency").await;
+
+    let create = |key: &'static str, amount: &'static str| {
+        client
+            .post("/transactions/create")
+            .header(Header::new("token", token.clone()))
+            .header(Header::new("Idempotency-Key", key))
+            .json(&json!({ "amount": amount, "txn_type": "purchase" }))
+            .dispatch()
+    };
+
+    let first = create("checkout-42", "12.50").await;
+    assert_eq!(first.status(), Status::Accepted);
+    let first: Value = first.into_json().await.unwrap();
+
+    // A retry gets the stored response back instead of a second txn.
+    let replay = create("checkout-42", "12.50").await;
+    assert_eq!(replay.status(), Status::Accepted);
+    let replay: Value = replay.into_json().await.unwrap();
+    assert_eq!(first, replay);
+
+    let mismatch = create("checkout-42", "99.00").await;
+    assert_eq!(mismatch.status(), Status::UnprocessableEntity);
+
+    // Two requests racing with one key still create a single txn.
+    let (a, b) = tokio::join!(create("checkout-43", "7.00"), create("checkout-43", "7.00"));
+    assert_eq!(a.status(), Status::Accepted);
+    assert_eq!(b.status(), Status::Accepted);
+    let a: Value = a.into_json().await.unwrap();
+    let b: Value = b.into_json().await.unwrap();
+    assert_eq!(a["transaction"]["txn_id"], b["transaction"]["txn_id"]);
+
+    let db = client.rocket().state::<DatabaseConnection>().unwrap();
+    let created = Txns::find()
+        .filter(txns::Column::AccountId.eq(first["transaction"]["account_id"].as_str().unwrap()))
+        .count(db)
+        .await
+        .unwrap();
+    assert_eq!(created, 2);
+
+    let queued = OutboxEvents::find()
+        .filter(outbox_events::Column::EventKey.eq(first["transaction"]["txn_id"].as_str().unwrap()))
+        .count(db)
+        .await
+        .unwrap();
+    assert_eq!(queued, 1);
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use common::{balances, register_and_login, wait_for_settlement};
+use payment_service::fx::file::FileRates;
+use payment_service::fx::table::StaticRates;
+use payment_service::fx::{self, FxRateProvider};
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::{Value, json};
+use rust_decimal::Decimal;
+
+fn dec(value: &str) -> Decimal {
+    Decimal::from_str(value).unwrap()
+}
+
+#[rocket::async_test]
+async fn static_rates_derive_cross_rates() {
+    let rates = StaticRates::default();
+
+    assert_eq!(rates.rate("USD", "INR").await.unwrap(), Some(dec("83.50")));
+    assert_eq!(rates.rate("INR", "INR").await.unwrap(), Some(Decimal::ONE));
+    assert_eq!(rates.rate("INR", "XYZ").await.unwrap(), None);
+
+    let eur_inr = rates.rate("EUR", "INR").await.unwrap().unwrap();
+    assert_eq!(eur_inr, (dec("83.50") / dec("0.92")).round_dp(fx::RATE_SCALE));
+}
+
+#[rocket::async_test]
+async fn file_rates_reload_when_the_file_changes() {
+    let path = std::env::temp_dir().join(format!(
+        "fx-rates-{}.json",
+        chrono::Utc::now().timestamp_nanos_opt().unwrap()
+    ));
+    std::fs::write(&path, r#"{"USD": "1", "INR": "80"}"#).unwrap();
+
+    let rates = FileRates::load(&path).unwrap();
+    assert_eq!(rates.rate("USD", "INR").await.unwrap(), Some(dec("80")));
+
+    std::fs::write(&path, r#"{"USD": "1", "INR": "90"}"#).unwrap();
+    // Bump the modification time explicitly; some filesystems only keep
+    // whole seconds.
+    std::fs::File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(5))
+        .unwrap();
+    assert_eq!(rates.rate("USD", "INR").await.unwrap(), Some(dec("90")));
+
+    std::fs::remove_file(&path).unwrap();
+    assert!(FileRates::load(&path).is_err());
+}
+
+#[test]
+fn conversion_truncates_to_the_target_minor_unit() {
+    assert_eq!(fx::convert(dec("1000"), dec("0.0119760479"), "USD"), dec("11.97"));
+    assert_eq!(fx::convert(dec("10"), dec("157.5"), "JPY"), dec("1575"));
+}
+
+async fn quote(client: &Client, token: &str, from: &str, to: &str) -> Value {
+    let response = client
+        .post("/fx/quotes")
+        .header(Header::new("token", token.to_string()))
+        .json(&json!({ "from_currency": from, "to_currency": to }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+    let body: Value = response.into_json().await.unwrap();
+    body["quote"].clone()
+}
+
+#[rocket::async_test]
+async fn exchange_debits_one_currency_and_credits_another_at_the_quoted_rate() {
+    let client = common::client().await;
+    let token = register_and_login(&client, "fx").await;
+    let auth = || Header::new("token", token.clone());
+
+    let response = client.get("/accounts").header(auth()).dispatch().await;
+    let listed: Value = response.into_json().await.unwrap();
+    let inr_id = listed["accounts"][0]["account_id"].as_str().unwrap().to_string();
+
+    let response = client
+        .post("/accounts")
+        .header(auth())
+        .json(&json!({ "currency_code": "USD" }))
+        .dispatch()
+        .await;
+    let opened: Value = response.into_json().await.unwrap();
+    let usd_id = opened["account"]["account_id"].as_str().unwrap().to_string();
+
+    let inr_opening = balances(&client, &token, Some(&inr_id)).await.0;
+    let quote = quote(&client, &token, "INR", "USD").await;
+    let rate = dec(quote["rate"].as_str().unwrap());
+
+    let response = client
+        .post("/transactions/create")
+        .header(auth())
+        .json(&json!({ "amount": "835.00", "txn_type": "exchange", "quote_id": quote["quote_id"] }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Accepted);
+    let created: Value = response.into_json().await.unwrap();
+    assert_eq!(created["transaction"]["account_id"], inr_id.as_str());
+    assert_eq!(created["transaction"]["counterparty_account_id"], usd_id.as_str());
+    assert_eq!(created["transaction"]["fx_rate"], quote["rate"]);
+
+    let txn_id = created["transaction"]["txn_id"].as_str().unwrap();
+    let settled = wait_for_settlement(&client, &token, txn_id).await;
+    assert_eq!(settled["transaction"]["status"], "succeeded");
+
+    assert_eq!(balances(&client, &token, Some(&inr_id)).await.0, inr_opening - dec("835.00"));
+    assert_eq!(
+        balances(&client, &token, Some(&usd_id)).await.0,
+        fx::convert(dec("835.00"), rate, "USD")
+    );
+
+    let response = client
+        .post("/transactions/create")
+        .header(auth())
+        .json(&json!({ "amount": "1.00", "txn_type": "exchange", "quote_id": quote["quote_id"] }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Conflict);
+}
+
+#[rocket::async_test]
+async fn exchange_needs_a_target_account() {
+    let client = common::client().await;
+    let token = register_and_login(&client, "fx-missing").await;
+
+    let quote = quote(&client, &token, "INR", "EUR").await;
+    let response = client
+        .post("/transactions/create")
+        .header(Header::new("token", token.clone()))
+        .json(&json!({ "amount": "10.00", "txn_type": "exchange", "quote_id": quote["quote_id"] }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+}
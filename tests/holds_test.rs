@@ -0,0 +1,160 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{balances, post_json, register_and_login};
+use payment_service::AppConfig;
+use payment_service::entities::prelude::*;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::{Value, json};
+use rust_decimal::Decimal;
+use sea_orm::*;
+
+async fn authorize(client: &Client, token: &str, amount: &str) -> String {
+    let (status, body) = post_json(client, token, "/transactions/authorize".to_string(), json!({ "amount": amount })).await;
+    assert_eq!(status, Status::Created);
+    assert_eq!(body["hold"]["status"], "active");
+    body["hold"]["hold_id"].as_str().unwrap().to_string()
+}
+
+#[rocket::async_test]
+async fn partial_capture_settles_and_releases_the_rest() {
+    let client = common::client().await;
+    let token = register_and_login(&client, "capture").await;
+    let (opening, _) = balances(&client, &token, None).await;
+
+    let hold_id = authorize(&client, &token, "60.00").await;
+    assert_eq!(balances(&client, &token, None).await, (opening, Decimal::from(60)));
+
+    let (status, body) = post_json(
+        &client,
+        &token,
+        format!("/transactions/holds/{}/capture", hold_id),
+        json!({ "amount": "45.00" }),
+    )
+    .await;
+    assert_eq!(status, Status::Ok);
+    assert_eq!(body["hold"]["status"], "captured");
+    assert!(body["hold"]["capture_txn_id"].is_string());
+    assert_eq!(
+        balances(&client, &token, None).await,
+        (opening - Decimal::from(45), Decimal::ZERO)
+    );
+
+    let (status, _) = post_json(&client, &token, format!("/transactions/holds/{}/void", hold_id), json!({})).await;
+    assert_eq!(status, Status::Conflict);
+}
+
+#[rocket::async_test]
+async fn void_releases_the_hold_and_held_funds_cannot_be_spent() {
+    let client = common::client().await;
+    let token = register_and_login(&client, "void").await;
+    let (opening, _) = balances(&client, &token, None).await;
+
+    let hold_id = authorize(&client, &token, &opening.to_string()).await;
+
+    let (status, body) = post_json(&client, &token, "/transactions/authorize".to_string(), json!({ "amount": "1.00" })).await;
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["reason"], "insufficient_funds");
+
+    let (status, body) = post_json(&client, &token, format!("/transactions/holds/{}/void", hold_id), json!({})).await;
+    assert_eq!(status, Status::Ok);
+    assert_eq!(body["hold"]["status"], "voided");
+    assert_eq!(balances(&client, &token, None).await, (opening, Decimal::ZERO));
+
+    let other = register_and_login(&client, "void-other").await;
+    let hold_id = authorize(&client, &token, "1.00").await;
+    let (status, _) = post_json(&client, &other, format!("/transactions/holds/{}/void", hold_id), json!({})).await;
+    assert_eq!(status, Status::NotFound);
+
+    // Another user's capture never waits on, or takes, the hold's lock.
+    let db = client.rocket().state::<DatabaseConnection>().unwrap();
+    let locker = db.begin().await.unwrap();
+    Holds::find_by_id(hold_id.clone())
+        .lock_exclusive()
+        .one(&locker)
+        .await
+        .unwrap();
+    let (status, _) = tokio::time::timeout(
+        Duration::from_secs(5),
+        post_json(&client, &other, format!("/transactions/holds/{}/capture", hold_id), json!({})),
+    )
+    .await
+    .expect("a foreign capture blocked on the hold's row lock");
+    assert_eq!(status, Status::NotFound);
+    locker.rollback().await.unwrap();
+}
+
+#[rocket::async_test]
+async fn retried_capture_and_void_replay_the_original_response() {
+    let client = common::client().await;
+    let token = register_and_login(&client, "hold-retry").await;
+    let (opening, _) = balances(&client, &token, None).await;
+
+    let send = |uri: String, key: &'static str, body: Value| {
+        client
+            .post(uri)
+            .header(Header::new("token", token.clone()))
+            .header(Header::new("Idempotency-Key", key))
+            .json(&body)
+            .dispatch()
+    };
+
+    let hold_id = authorize(&client, &token, "30.00").await;
+    let capture = format!("/transactions/holds/{}/capture", hold_id);
+    let first = send(capture.clone(), "capture-1", json!({ "amount": "20.00" })).await;
+    assert_eq!(first.status(), Status::Ok);
+    let first: Value = first.into_json().await.unwrap();
+
+    let retry = send(capture.clone(), "capture-1", json!({ "amount": "20.00" })).await;
+    assert_eq!(retry.status(), Status::Ok);
+    assert_eq!(retry.into_json::<Value>().await.unwrap(), first);
+    assert_eq!(
+        balances(&client, &token, None).await,
+        (opening - Decimal::from(20), Decimal::ZERO)
+    );
+
+    let mismatch = send(capture, "capture-1", json!({ "amount": "10.00" })).await;
+    assert_eq!(mismatch.status(), Status::UnprocessableEntity);
+
+    let hold_id = authorize(&client, &token, "5.00").await;
+    let void = format!("/transactions/holds/{}/void", hold_id);
+    let first = send(void.clone(), "void-1", json!({})).await;
+    assert_eq!(first.status(), Status::Ok);
+    let first: Value = first.into_json().await.unwrap();
+    let retry = send(void, "void-1", json!({})).await;
+    assert_eq!(retry.status(), Status::Ok);
+    assert_eq!(retry.into_json::<Value>().await.unwrap(), first);
+}
+
+#[rocket::async_test]
+async fn sweeper_expires_stale_holds() {
+    let client = common::client_with(AppConfig {
+        hold_ttl_secs: 0,
+        hold_sweep_interval_secs: 1,
+        ..common::test_config()
+    })
+    .await;
+    let token = register_and_login(&client, "expiry").await;
+    let (opening, _) = balances(&client, &token, None).await;
+
+    let hold_id = authorize(&client, &token, "10.00").await;
+
+    let (status, _) = post_json(
+        &client,
+        &token,
+        format!("/transactions/holds/{}/capture", hold_id),
+        json!({}),
+    )
+    .await;
+    assert_eq!(status, Status::Conflict);
+
+    for _ in 0..30 {
+        if balances(&client, &token, None).await == (opening, Decimal::ZERO) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    panic!("hold {} was never released", hold_id);
+}
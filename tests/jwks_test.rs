@@ -0,0 +1,143 @@
+mod common;
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use common::{fixture, me, register, unique_email};
+use jsonwebtoken::{Algorithm, EncodingKey, Header, encode};
+use payment_service::AppConfig;
+use rocket::http::Status;
+use rocket::serde::json::{Value, json};
+
+fn now() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
+
+/// Claims of a token the service issued, read without verifying it, with
+/// a fresh validity window.
+fn claims(token: &str) -> Value {
+    let payload = token.split('.').nth(1).unwrap();
+    let mut claims: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap();
+    claims["iat"] = json!(now());
+    claims["nbf"] = json!(now());
+    claims["exp"] = json!(now() + 600);
+    claims
+}
+
+fn sign(kid: &str, pem_file: &str, claims: &Value) -> String {
+    let mut header = Header::new(Algorithm::EdDSA);
+    header.kid = Some(kid.to_string());
+    let pem = std::fs::read(fixture(pem_file)).unwrap();
+    encode(&header, claims, &EncodingKey::from_ed_pem(&pem).unwrap()).unwrap()
+}
+
+#[rocket::async_test]
+async fn jwks_lists_every_verification_key() {
+    let client = common::client().await;
+
+    let response = client.get("/.well-known/jwks.json").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let jwks: Value = response.into_json().await.unwrap();
+
+    let kids: Vec<&str> = jwks["keys"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|key| key["kid"].as_str().unwrap())
+        .collect();
+    assert_eq!(kids, ["2025-06-01", "2025-07-01", "2025-08-01"]);
+    for key in jwks["keys"].as_array().unwrap() {
+        assert_eq!(key["kty"], "OKP");
+        assert_eq!(key["crv"], "Ed25519");
+        assert_eq!(key["alg"], "EdDSA");
+        assert_eq!(key["use"], "sig");
+        assert!(key.get("d").is_none());
+    }
+}
+
+#[rocket::async_test]
+async fn tokens_from_rotated_keys_still_verify() {
+    // An instance still signing with the older key...
+    let old = common::client_with(AppConfig {
+        jwt_signing_kid: Some("2025-07-01".to_string()),
+        ..common::test_config()
+    })
+    .await;
+    let email = unique_email("rotation");
+    register(&old, &email).await;
+    let token = common::login(&old, &email).await;
+    let header = jsonwebtoken::decode_header(&token).unwrap();
+    assert_eq!(header.alg, Algorithm::EdDSA);
+    assert_eq!(header.kid.as_deref(), Some("2025-07-01"));
+
+    // ...and one on the newest key both accept its tokens.
+    let new = common::client().await;
+    assert_eq!(me(&new, &token).await, Status::Ok);
+
+    // So are tokens from a retired key whose public half is still listed.
+    let retired = sign("2025-06-01", "retired_2025-06-01.pem", &claims(&token));
+    assert_eq!(me(&new, &retired).await, Status::Ok);
+
+    // A kid the service does not know is rejected.
+    let unknown = sign("2024-01-01", "retired_2025-06-01.pem", &claims(&token));
+    assert_eq!(me(&new, &unknown).await, Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn registered_claims_are_checked() {
+    let client = common::client().await;
+    let token = common::register_and_login(&client, "claims").await;
+    let resigned = sign("2025-08-01", "jwt_keys/2025-08-01.pem", &claims(&token));
+    assert_eq!(me(&client, &resigned).await, Status::Ok);
+
+    let broken = [
+        ("iss", json!("someone-else")),
+        ("aud", json!("another-api")),
+        ("nbf", json!(now() + 3600)),
+        ("iat", json!(now() + 3600)),
+        ("exp", json!(now() - 3600)),
+    ];
+    for (claim, value) in broken {
+        let mut claims = claims(&token);
+        claims[claim] = value;
+        let token = sign("2025-08-01", "jwt_keys/2025-08-01.pem", &claims);
+        assert_eq!(me(&client, &token).await, Status::Unauthorized, "{}", claim);
+    }
+
+    // A shared-secret token is refused however it is labelled.
+    let mut header = Header::new(Algorithm::HS256);
+    header.kid = Some("2025-08-01".to_string());
+    let hs256 = encode(&header, &claims(&token), &EncodingKey::from_secret(b"secret")).unwrap();
+    assert_eq!(me(&client, &hs256).await, Status::Unauthorized);
+
+    // Another audience's instance does not accept this one's tokens.
+    let other = common::client_with(AppConfig {
+        jwt_audience: "another-api".to_string(),
+        ..common::test_config()
+    })
+    .await;
+    assert_eq!(me(&other, &token).await, Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn tokens_are_only_accepted_for_their_purpose() {
+    let client = common::client().await;
+    let email = unique_email("purpose");
+    register(&client, &email).await;
+    let token = common::login(&client, &email).await;
+    assert_eq!(claims(&token)["purpose"], "access");
+
+    // Even with every access claim present, another purpose, or none, is refused.
+    for purpose in [json!("mfa"), json!("verify_email"), json!("reset_password"), Value::Null] {
+        let mut claims = claims(&token);
+        claims["purpose"] = purpose.clone();
+        let token = sign("2025-08-01", "jwt_keys/2025-08-01.pem", &claims);
+        assert_eq!(me(&client, &token).await, Status::Unauthorized, "{}", purpose);
+    }
+
+    // And an access token is not an email token.
+    let response = client
+        .post("/auth/reset-password")
+        .json(&json!({ "token": token, "password": "Another-Horse-42" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::register_and_login;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::{Value, json};
+
+async fn create(client: &Client, token: &str, amount: &str, txn_type: &str) -> String {
+    let response = client
+        .post("/transactions/create")
+        .header(Header::new("token", token.to_string()))
+        .json(&json!({ "amount": amount, "txn_type": txn_type }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Accepted);
+    let created: Value = response.into_json().await.unwrap();
+    created["transaction"]["txn_id"].as_str().unwrap().to_string()
+}
+
+async fn list(client: &Client, token: &str, query: &str) -> (Status, Value) {
+    let response = client
+        .get(format!("/transactions/list?{}", query))
+        .header(Header::new("token", token.to_string()))
+        .dispatch()
+        .await;
+    let status = response.status();
+    let body = response
+        .into_json()
+        .await
+        .unwrap_or_else(|| json!({}));
+    (status, body)
+}
+
+fn ids(body: &Value) -> Vec<String> {
+    body["transactions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["txn_id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[rocket::async_test]
+async fn empty_history_is_an_empty_page() {
+    let client = common::client().await;
+    let token = register_and_login(&client, "list-empty").await;
+
+    let (status, body) = list(&client, &token, "").await;
+    assert_eq!(status, Status::Ok);
+    assert_eq!(body["transactions"], json!([]));
+    assert!(body["next_cursor"].is_null());
+}
+
+#[rocket::async_test]
+async fn cursor_walks_every_transaction_newest_first() {
+    let client = common::client().await;
+    let token = register_and_login(&client, "list-pages").await;
+
+    let mut created = Vec::new();
+    for amount in ["1.00", "2.00", "3.00", "4.00", "5.00"] {
+        created.push(create(&client, &token, amount, "credit").await);
+    }
+    created.reverse();
+
+    let mut seen = Vec::new();
+    let mut query = "limit=2".to_string();
+    loop {
+        let (status, body) = list(&client, &token, &query).await;
+        assert_eq!(status, Status::Ok);
+        let page = ids(&body);
+        assert!(page.len() <= 2);
+        seen.extend(page);
+        match body["next_cursor"].as_str() {
+            Some(cursor) => query = format!("limit=2&cursor={}", cursor),
+            None => break,
+        }
+    }
+
+    assert_eq!(seen, created);
+}
+
+#[rocket::async_test]
+async fn filters_narrow_the_list() {
+    let client = common::client().await;
+    let token = register_and_login(&client, "list-filter").await;
+
+    create(&client, &token, "10.00", "credit").await;
+    let purchase = create(&client, &token, "20.00", "purchase").await;
+    create(&client, &token, "30.00", "purchase").await;
+
+    let (_, body) = list(&client, &token, "txn_type=purchase&max_amount=25").await;
+    assert_eq!(ids(&body), vec![purchase]);
+
+    let (_, body) = list(&client, &token, "min_amount=15").await;
+    assert_eq!(ids(&body).len(), 2);
+
+    let (_, body) = list(&client, &token, "from=2999-01-01T00:00:00Z").await;
+    assert!(ids(&body).is_empty());
+
+    let (status, _) = list(&client, &token, "status=bogus").await;
+    assert_eq!(status, Status::UnprocessableEntity);
+
+    let (status, _) = list(&client, &token, "cursor=not-a-cursor").await;
+    assert_eq!(status, Status::UnprocessableEntity);
+}
@@ -0,0 +1,304 @@
+mod common;
+
+use common::{PASSWORD, me, register, unique_email};
+use payment_service::AppConfig;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::{Value, json};
+use rust_decimal::Decimal;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+/// Code for the time step `offset` steps from now. Each use of a code must
+/// be at a later step than the last, so tests move forward one step at a
+/// time.
+fn code(secret: &str, offset: i64) -> String {
+    let totp = TOTP::new_unchecked(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        Secret::Encoded(secret.to_string()).to_bytes().unwrap(),
+        None,
+        "test".to_string(),
+    );
+    let now = chrono::Utc::now().timestamp() + offset * 30;
+    totp.generate(now as u64)
+}
+
+async fn password_login(client: &Client, email: &str) -> Value {
+    let response = client
+        .post("/auth/login")
+        .json(&json!({ "email": email, "password": PASSWORD }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    response.into_json().await.unwrap()
+}
+
+/// Registers a user with MFA on. Returns their email, TOTP secret
+/// and recovery codes; the last code used was one step in the past.
+async fn enrolled_user(client: &Client, prefix: &str) -> (String, String, Vec<String>) {
+    let email = unique_email(prefix);
+    register(client, &email).await;
+    let token = password_login(client, &email).await["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let response = client
+        .post("/auth/mfa/enroll")
+        .header(Header::new("token", token.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let enrolled: Value = response.into_json().await.unwrap();
+    let secret = enrolled["secret"].as_str().unwrap().to_string();
+    assert!(enrolled["otpauth_uri"].as_str().unwrap().starts_with("otpauth://totp/"));
+
+    let response = client
+        .post("/auth/mfa/activate")
+        .header(Header::new("token", token.clone()))
+        .json(&json!({ "code": code(&secret, -1) }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let activated: Value = response.into_json().await.unwrap();
+    let recovery_codes = activated["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c.as_str().unwrap().to_string())
+        .collect();
+
+    (email, secret, recovery_codes)
+}
+
+async fn verify(client: &Client, mfa_token: &str, code: &str) -> Result<String, Status> {
+    let response = client
+        .post("/auth/mfa/verify")
+        .json(&json!({ "mfa_token": mfa_token, "code": code }))
+        .dispatch()
+        .await;
+    if response.status() != Status::Ok {
+        return Err(response.status());
+    }
+    let body: Value = response.into_json().await.unwrap();
+    Ok(body["token"].as_str().unwrap().to_string())
+}
+
+#[rocket::async_test]
+async fn login_with_mfa_needs_a_code() {
+    // No backoff, so the wrong code below does not block the right one.
+    let client = common::client_with(AppConfig {
+        login_backoff_base_secs: 0,
+        ..common::test_config()
+    })
+    .await;
+    let (email, secret, _) = enrolled_user(&client, "mfa-login").await;
+
+    let challenge = password_login(&client, &email).await;
+    assert_eq!(challenge["status"], "mfa_required");
+    assert!(challenge.get("token").is_none());
+    let mfa_token = challenge["mfa_token"].as_str().unwrap();
+
+    // The pending token is not an access token.
+    assert_eq!(me(&client, mfa_token).await, Status::Unauthorized);
+
+    assert_eq!(verify(&client, mfa_token, "000000").await.err(), Some(Status::Unauthorized));
+
+    let token = verify(&client, mfa_token, &code(&secret, 0)).await.unwrap();
+    assert_eq!(me(&client, &token).await, Status::Ok);
+
+    // A code cannot be replayed.
+    assert_eq!(
+        verify(&client, mfa_token, &code(&secret, 0)).await.err(),
+        Some(Status::Unauthorized)
+    );
+}
+
+#[rocket::async_test]
+async fn recovery_codes_work_once() {
+    let client = common::client().await;
+    let (email, _, recovery_codes) = enrolled_user(&client, "mfa-recovery").await;
+    assert_eq!(recovery_codes.len(), 10);
+
+    let challenge = password_login(&client, &email).await;
+    let mfa_token = challenge["mfa_token"].as_str().unwrap();
+
+    let token = verify(&client, mfa_token, &recovery_codes[0].to_lowercase())
+        .await
+        .unwrap();
+    assert_eq!(
+        verify(&client, mfa_token, &recovery_codes[0]).await.err(),
+        Some(Status::Unauthorized)
+    );
+
+    let response = client
+        .get("/auth/mfa")
+        .header(Header::new("token", token))
+        .dispatch()
+        .await;
+    let status: Value = response.into_json().await.unwrap();
+    assert_eq!(status["enabled"], true);
+    assert_eq!(status["recovery_codes_remaining"], 9);
+}
+
+#[rocket::async_test]
+async fn large_debits_need_step_up() {
+    // No backoff, so the wrong code below does not block the right one.
+    let client = common::client_with(AppConfig {
+        mfa_step_up_amount: Decimal::new(100, 0),
+        login_backoff_base_secs: 0,
+        ..common::test_config()
+    })
+    .await;
+
+    let create = |token: String, amount: &'static str, code: Option<String>| {
+        let mut request = client
+            .post("/transactions/create")
+            .header(Header::new("token", token))
+            .json(&json!({ "amount": amount, "txn_type": "purchase" }));
+        if let Some(code) = code {
+            request = request.header(Header::new("X-MFA-Code", code));
+        }
+        request.dispatch()
+    };
+
+    // Without MFA only small amounts go through.
+    let plain_token = common::register_and_login(&client, "mfa-plain").await;
+    assert_eq!(create(plain_token.clone(), "50.00", None).await.status(), Status::Accepted);
+    assert_eq!(create(plain_token, "150.00", None).await.status(), Status::Forbidden);
+
+    let (email, secret, recovery_codes) = enrolled_user(&client, "mfa-step-up").await;
+    let challenge = password_login(&client, &email).await;
+    let token = verify(&client, challenge["mfa_token"].as_str().unwrap(), &code(&secret, 0))
+        .await
+        .unwrap();
+
+    assert_eq!(create(token.clone(), "150.00", None).await.status(), Status::Forbidden);
+    assert_eq!(
+        create(token.clone(), "150.00", Some("123456".to_string())).await.status(),
+        Status::Forbidden
+    );
+    assert_eq!(
+        create(token.clone(), "150.00", Some(code(&secret, 1))).await.status(),
+        Status::Accepted
+    );
+
+    // Capturing a large hold needs a code too, not just placing it. The
+    // TOTP codes within the allowed skew are spent, so recovery codes stand in.
+    let post = |uri: String, body: Value, code: Option<String>| {
+        let mut request = client
+            .post(uri)
+            .header(Header::new("token", token.clone()))
+            .json(&body);
+        if let Some(code) = code {
+            request = request.header(Header::new("X-MFA-Code", code));
+        }
+        request.dispatch()
+    };
+    let response = post(
+        "/transactions/authorize".to_string(),
+        json!({ "amount": "150.00" }),
+        Some(recovery_codes[0].clone()),
+    )
+    .await;
+    assert_eq!(response.status(), Status::Created);
+    let held: Value = response.into_json().await.unwrap();
+    let capture = format!("/transactions/holds/{}/capture", held["hold"]["hold_id"].as_str().unwrap());
+    assert_eq!(post(capture.clone(), json!({}), None).await.status(), Status::Forbidden);
+    assert_eq!(
+        post(capture, json!({}), Some(recovery_codes[1].clone())).await.status(),
+        Status::Ok
+    );
+}
+
+#[rocket::async_test]
+async fn wrong_step_up_codes_are_throttled() {
+    let client = common::client_with(AppConfig {
+        mfa_step_up_amount: Decimal::new(100, 0),
+        login_backoff_base_secs: 0,
+        login_max_failures: 3,
+        ..common::test_config()
+    })
+    .await;
+
+    let (email, secret, _) = enrolled_user(&client, "mfa-throttle").await;
+    let challenge = password_login(&client, &email).await;
+    let token = verify(&client, challenge["mfa_token"].as_str().unwrap(), &code(&secret, 0))
+        .await
+        .unwrap();
+
+    let debit = |uri: &'static str, code: String| {
+        client
+            .post(uri)
+            .header(Header::new("token", token.clone()))
+            .header(Header::new("X-MFA-Code", code))
+            .json(&json!({ "amount": "150.00", "txn_type": "purchase" }))
+            .dispatch()
+    };
+
+    // Guesses on different routes count against the same limit.
+    let wrong = || "000000".to_string();
+    assert_eq!(debit("/transactions/create", wrong()).await.status(), Status::Forbidden);
+    assert_eq!(debit("/transactions/authorize", wrong()).await.status(), Status::Forbidden);
+    assert_eq!(debit("/transactions/create", wrong()).await.status(), Status::Forbidden);
+
+    let response = debit("/transactions/create", wrong()).await;
+    assert_eq!(response.status(), Status::TooManyRequests);
+    let body: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+    assert_eq!(body["status"], "mfa_throttled");
+
+    // Once locked out, even a right code is not checked.
+    assert_eq!(
+        debit("/transactions/create", code(&secret, 1)).await.status(),
+        Status::TooManyRequests
+    );
+    let response = client
+        .post("/auth/mfa/disable")
+        .header(Header::new("token", token.clone()))
+        .json(&json!({ "code": code(&secret, 1) }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::TooManyRequests);
+}
+
+#[rocket::async_test]
+async fn step_up_threshold_is_converted_from_the_account_currency() {
+    let client = common::client_with(AppConfig {
+        mfa_step_up_amount: Decimal::new(100, 0),
+        mfa_step_up_currency: "INR".to_string(),
+        ..common::test_config()
+    })
+    .await;
+    let token = common::register_and_login(&client, "mfa-currency").await;
+
+    let mut account_ids = Vec::new();
+    for currency in ["USD", "JPY"] {
+        let response = client
+            .post("/accounts")
+            .header(Header::new("token", token.clone()))
+            .json(&json!({ "currency_code": currency }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Created);
+        let opened: Value = response.into_json().await.unwrap();
+        account_ids.push(opened["account"]["account_id"].as_str().unwrap().to_string());
+    }
+    let [usd, jpy] = &account_ids[..] else { unreachable!() };
+
+    let create = |account_id: &str, amount: &'static str| {
+        client
+            .post("/transactions/create")
+            .header(Header::new("token", token.clone()))
+            .json(&json!({ "amount": amount, "txn_type": "purchase", "account_id": account_id }))
+            .dispatch()
+    };
+
+    // 2 USD is about 167 INR, over the limit despite the small number.
+    assert_eq!(create(usd, "2.00").await.status(), Status::Forbidden);
+    assert_eq!(create(usd, "1.00").await.status(), Status::Accepted);
+    // 150 JPY is about 80 INR, under the limit despite the large number.
+    assert_eq!(create(jpy, "150").await.status(), Status::Accepted);
+    assert_eq!(create(jpy, "250").await.status(), Status::Forbidden);
+}
@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use chrono::{FixedOffset, Utc};
+use payment_service::entities::sea_orm_active_enums::TxnStatus;
+use payment_service::entities::{account, txns};
+use payment_service::processing::decision::reason;
+use payment_service::processing::rules::RulesProcessor;
+use payment_service::processing::simulated::SimulatedGateway;
+use payment_service::processing::{Decision, TransactionProcessor};
+use rust_decimal::Decimal;
+
+fn rules() -> RulesProcessor {
+    RulesProcessor {
+        max_txn_amount: Decimal::new(1_000, 0),
+    }
+}
+
+fn now() -> chrono::DateTime<FixedOffset> {
+    Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap())
+}
+
+fn account(balance: i64, status: &str) -> account::Model {
+    account::Model {
+        account_id: "acc-test".to_string(),
+        user_id: "user-test".to_string(),
+        currency_code: "INR".to_string(),
+        balance: Decimal::new(balance, 0),
+        locked_balance: Decimal::ZERO,
+        updated_at: now(),
+        status: status.to_string(),
+    }
+}
+
+fn txn(amount: i64, txn_type: &str) -> txns::Model {
+    txns::Model {
+        txn_id: "tx-test".to_string(),
+        account_id: "acc-test".to_string(),
+        amount: Decimal::new(amount, 0),
+        currency_code: "INR".to_string(),
+        txn_type: txn_type.to_string(),
+        status: TxnStatus::Pending,
+        created_at: now(),
+        failure_reason: None,
+        parent_txn_id: None,
+        counterparty_account_id: None,
+        fx_rate: None,
+    }
+}
+
+fn reason_of(decision: Decision) -> Option<&'static str> {
+    match decision {
+        Decision::Approved => None,
+        Decision::Declined { reason_code, .. } => Some(reason_code),
+    }
+}
+
+#[rocket::async_test]
+async fn rules_processor_checks_funds_limits_and_account_state() {
+    let rules = rules();
+
+    assert_eq!(rules.decide(&txn(50, "purchase"), &account(100, "active")).await, Decision::Approved);
+    assert_eq!(rules.decide(&txn(500, "credit"), &account(0, "active")).await, Decision::Approved);
+    assert_eq!(rules.decide(&txn(500, "refund"), &account(0, "active")).await, Decision::Approved);
+    assert_eq!(
+        reason_of(rules.decide(&txn(150, "purchase"), &account(100, "active")).await),
+        Some(reason::INSUFFICIENT_FUNDS)
+    );
+    assert_eq!(
+        reason_of(rules.decide(&txn(5_000, "credit"), &account(100, "active")).await),
+        Some(reason::AMOUNT_OVER_LIMIT)
+    );
+    assert_eq!(
+        reason_of(rules.decide(&txn(10, "purchase"), &account(100, "frozen")).await),
+        Some(reason::ACCOUNT_INACTIVE)
+    );
+
+    let held = account::Model {
+        locked_balance: Decimal::new(60, 0),
+        ..account(100, "active")
+    };
+    assert_eq!(
+        reason_of(rules.decide(&txn(50, "purchase"), &held).await),
+        Some(reason::INSUFFICIENT_FUNDS)
+    );
+    assert_eq!(rules.decide(&txn(40, "purchase"), &held).await, Decision::Approved);
+}
+
+#[rocket::async_test]
+async fn simulated_gateway_is_reproducible_for_a_seed() {
+    let run = |seed| async move {
+        let gateway = SimulatedGateway::new(rules(), 0.5, Duration::ZERO, seed);
+        let mut outcomes = Vec::new();
+        for _ in 0..32 {
+            outcomes.push(reason_of(gateway.authorize(&txn(10, "purchase"), &account(100, "active")).await));
+        }
+        outcomes
+    };
+
+    let first = run(7).await;
+    assert_eq!(first, run(7).await);
+    assert!(first.iter().any(Option::is_none));
+    assert!(first.iter().any(Option::is_some));
+}
+
+#[rocket::async_test]
+async fn simulated_gateway_still_applies_rules() {
+    let gateway = SimulatedGateway::new(rules(), 0.0, Duration::ZERO, 1);
+
+    assert_eq!(gateway.authorize(&txn(10, "purchase"), &account(100, "active")).await, Decision::Approved);
+    assert_eq!(
+        reason_of(gateway.authorize(&txn(150, "purchase"), &account(100, "active")).await),
+        Some(reason::INSUFFICIENT_FUNDS)
+    );
+
+    // Under the lock only the rules run, against the balance as it is then.
+    let always_declines = SimulatedGateway::new(rules(), 1.0, Duration::ZERO, 1);
+    assert_eq!(always_declines.decide(&txn(10, "purchase"), &account(100, "active")).await, Decision::Approved);
+    assert_eq!(
+        reason_of(always_declines.decide(&txn(150, "purchase"), &account(100, "active")).await),
+        Some(reason::INSUFFICIENT_FUNDS)
+    );
+}
@@ -0,0 +1,256 @@
+mod common;
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use common::{balance, register_and_login, wait_for_settlement};
+use payment_service::entities::prelude::*;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::{Value, json};
+use rust_decimal::Decimal;
+use sea_orm::*;
+
+#[rocket::async_test]
+async fn create_process_and_check_status_without_kafka() {
+    let client = common::client().await;
+    let token = register_and_login(&client, "flow").await;
+    let opening = balance(&client, &token).await;
+
+    let response = client
+        .post("/transactions/create")
+        .header(Header::new("token", token.clone()))
+        .json(&json!({ "amount": "25.50", "txn_type": "purchase" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Accepted);
+    let created: Value = response.into_json().await.unwrap();
+    let txn_id = created["transaction"]["txn_id"].as_str().unwrap();
+
+    let settled = wait_for_settlement(&client, &token, txn_id).await;
+    assert_eq!(settled["transaction"]["status"], "succeeded");
+    assert_eq!(
+        balance(&client, &token).await,
+        opening - Decimal::from_str("25.50").unwrap()
+    );
+}
+
+#[rocket::async_test]
+async fn purchase_above_balance_is_declined_with_reason() {
+    let client = common::client().await;
+    let token = register_and_login(&client, "declined").await;
+    let opening = balance(&client, &token).await;
+
+    let response = client
+        .post("/transactions/create")
+        .header(Header::new("token", token.clone()))
+        .json(&json!({ "amount": "9000.00", "txn_type": "purchase" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Accepted);
+    let created: Value = response.into_json().await.unwrap();
+    let txn_id = created["transaction"]["txn_id"].as_str().unwrap();
+
+    let settled = wait_for_settlement(&client, &token, txn_id).await;
+    assert_eq!(settled["transaction"]["status"], "failed");
+    assert_eq!(balance(&client, &token).await, opening);
+
+    assert_eq!(settled["failure_reason"], "insufficient_funds");
+    assert_eq!(settled["failure_message"], "Insufficient balance");
+
+    let timeline = settled["timeline"].as_array().unwrap();
+    assert_eq!(timeline.len(), 3);
+    assert_eq!(timeline[0]["from_status"], Value::Null);
+    assert_eq!(timeline[0]["to_status"], "pending");
+    assert!(timeline[0]["actor"].as_str().unwrap().starts_with("user:"));
+    assert_eq!(timeline[1]["from_status"], "pending");
+    assert_eq!(timeline[1]["to_status"], "processing");
+    assert_eq!(timeline[2]["from_status"], "processing");
+    assert_eq!(timeline[2]["to_status"], "failed");
+    assert_eq!(timeline[2]["reason_code"], "insufficient_funds");
+    assert_eq!(timeline[2]["actor"], "processor");
+}
+
+async fn refund(client: &Client, token: &str, txn_id: &str, body: Value) -> (Status, Value) {
+    let response = client
+        .post(format!("/transactions/{}/refund", txn_id))
+        .header(Header::new("token", token.to_string()))
+        .json(&body)
+        .dispatch()
+        .await;
+    let status = response.status();
+    let body = response.into_json().await.unwrap_or(Value::Null);
+    (status, body)
+}
+
+#[rocket::async_test]
+async fn partial_then_full_refund_reverses_the_purchase() {
+    let client = common::client().await;
+    let token = register_and_login(&client, "refund").await;
+    let opening = balance(&client, &token).await;
+
+    let response = client
+        .post("/transactions/create")
+        .header(Header::new("token", token.clone()))
+        .json(&json!({ "amount": "40.00", "txn_type": "purchase" }))
+        .dispatch()
+        .await;
+    let created: Value = response.into_json().await.unwrap();
+    let purchase_id = created["transaction"]["txn_id"].as_str().unwrap().to_string();
+    wait_for_settlement(&client, &token, &purchase_id).await;
+
+    let (status, partial) = refund(&client, &token, &purchase_id, json!({ "amount": "15.00" })).await;
+    assert_eq!(status, Status::Accepted);
+    assert_eq!(partial["transaction"]["txn_type"], "refund");
+    assert_eq!(partial["transaction"]["parent_txn_id"], purchase_id.as_str());
+    let partial_id = partial["transaction"]["txn_id"].as_str().unwrap();
+    let settled = wait_for_settlement(&client, &token, partial_id).await;
+    assert_eq!(settled["transaction"]["status"], "succeeded");
+
+    let (status, _) = refund(&client, &token, &purchase_id, json!({ "amount": "25.01" })).await;
+    assert_eq!(status, Status::UnprocessableEntity);
+
+    let (status, rest) = refund(&client, &token, &purchase_id, json!({})).await;
+    assert_eq!(status, Status::Accepted);
+    assert_eq!(rest["transaction"]["amount"], "25.0000");
+    let rest_id = rest["transaction"]["txn_id"].as_str().unwrap();
+    wait_for_settlement(&client, &token, rest_id).await;
+
+    let original = wait_for_settlement(&client, &token, &purchase_id).await;
+    assert_eq!(original["transaction"]["status"], "reversed");
+    assert_eq!(balance(&client, &token).await, opening);
+
+    let (status, _) = refund(&client, &token, &purchase_id, json!({})).await;
+    assert_eq!(status, Status::Conflict);
+}
+
+#[rocket::async_test]
+async fn cannot_refund_another_users_purchase() {
+    let client = common::client().await;
+    let owner = register_and_login(&client, "refund-owner").await;
+    let other = register_and_login(&client, "refund-other").await;
+
+    let response = client
+        .post("/transactions/create")
+        .header(Header::new("token", owner.clone()))
+        .json(&json!({ "amount": "5.00", "txn_type": "purchase" }))
+        .dispatch()
+        .await;
+    let created: Value = response.into_json().await.unwrap();
+    let purchase_id = created["transaction"]["txn_id"].as_str().unwrap().to_string();
+    wait_for_settlement(&client, &owner, &purchase_id).await;
+
+    let (status, _) = refund(&client, &other, &purchase_id, json!({})).await;
+    assert_eq!(status, Status::NotFound);
+
+    // Another user's refund never waits on, or takes, the purchase's lock.
+    let db = client.rocket().state::<DatabaseConnection>().unwrap();
+    let locker = db.begin().await.unwrap();
+    Txns::find_by_id(purchase_id.clone())
+        .lock_exclusive()
+        .one(&locker)
+        .await
+        .unwrap();
+    let (status, _) = tokio::time::timeout(
+        Duration::from_secs(5),
+        refund(&client, &other, &purchase_id, json!({})),
+    )
+    .await
+    .expect("a foreign refund blocked on the purchase's row lock");
+    assert_eq!(status, Status::NotFound);
+    locker.rollback().await.unwrap();
+}
+
+#[rocket::async_test]
+async fn status_lookup_is_scoped_to_the_owner() {
+    let client = common::client().await;
+    let owner = register_and_login(&client, "status-owner").await;
+    let other = register_and_login(&client, "status-other").await;
+
+    let response = client
+        .post("/transactions/create")
+        .header(Header::new("token", owner.clone()))
+        .json(&json!({ "amount": "5.00", "txn_type": "purchase" }))
+        .dispatch()
+        .await;
+    let created: Value = response.into_json().await.unwrap();
+    let txn_id = created["transaction"]["txn_id"].as_str().unwrap().to_string();
+    wait_for_settlement(&client, &owner, &txn_id).await;
+
+    let status_of = |uri: String, token: Option<String>| {
+        let mut request = client.get(uri);
+        if let Some(token) = token {
+            request = request.header(Header::new("token", token));
+        }
+        async move { request.dispatch().await.status() }
+    };
+
+    let uri = format!("/transactions/status/{}", txn_id);
+    assert_eq!(status_of(uri.clone(), None).await, Status::Unauthorized);
+    assert_eq!(status_of(uri.clone(), Some(other.clone())).await, Status::NotFound);
+    assert_eq!(status_of(uri, Some(owner.clone())).await, Status::Ok);
+
+    let unknown = "/transactions/status/tx-00000000-0000-0000-0000-000000000000".to_string();
+    assert_eq!(status_of(unknown, Some(owner.clone())).await, Status::NotFound);
+
+    let malformed = "/transactions/status/not-a-txn".to_string();
+    assert_eq!(status_of(malformed, Some(owner)).await, Status::UnprocessableEntity);
+}
+
+#[rocket::async_test]
+async fn transfer_by_email_moves_money_and_links_both_sides() {
+    let client = common::client().await;
+    let sender_email = common::unique_email("transfer-from");
+    common::register(&client, &sender_email).await;
+    let sender = common::login(&client, &sender_email).await;
+    let recipient_email = common::unique_email("transfer-to");
+    common::register(&client, &recipient_email).await;
+    let recipient = common::login(&client, &recipient_email).await;
+
+    let sender_opening = balance(&client, &sender).await;
+    let recipient_opening = balance(&client, &recipient).await;
+
+    let response = client
+        .post("/transactions/create")
+        .header(Header::new("token", sender.clone()))
+        .json(&json!({ "amount": "30.00", "txn_type": "transfer", "to_email": recipient_email }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Accepted);
+    let created: Value = response.into_json().await.unwrap();
+    let transfer_id = created["transaction"]["txn_id"].as_str().unwrap().to_string();
+
+    let settled = wait_for_settlement(&client, &sender, &transfer_id).await;
+    assert_eq!(settled["transaction"]["status"], "succeeded");
+
+    let amount = Decimal::from_str("30.00").unwrap();
+    assert_eq!(balance(&client, &sender).await, sender_opening - amount);
+    assert_eq!(balance(&client, &recipient).await, recipient_opening + amount);
+
+    let response = client
+        .get("/transactions/list")
+        .header(Header::new("token", recipient.clone()))
+        .dispatch()
+        .await;
+    let listed: Value = response.into_json().await.unwrap();
+    let incoming = &listed["transactions"][0];
+    assert_eq!(incoming["txn_type"], "transfer_in");
+    assert_eq!(incoming["parent_txn_id"], transfer_id.as_str());
+    assert_eq!(incoming["counterparty_account_id"], settled["transaction"]["account_id"]);
+
+    let response = client
+        .post("/transactions/create")
+        .header(Header::new("token", sender.clone()))
+        .json(&json!({ "amount": "1.00", "txn_type": "transfer", "to_email": sender_email }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response = client
+        .post("/transactions/create")
+        .header(Header::new("token", sender.clone()))
+        .json(&json!({ "amount": "1.00", "txn_type": "transfer", "to_email": "nobody@example.com" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+}